@@ -0,0 +1,162 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// A file that is written next to its destination under a temporary name and only renamed over
+/// the destination on `commit`, so readers never see a half written file. When the destination
+/// already exists its permissions are kept.
+pub struct AtomicFile {
+    path: PathBuf,
+    temp_path: PathBuf,
+    file: Option<File>,
+}
+
+impl AtomicFile {
+    pub fn create(path: &Path) -> io::Result<AtomicFile> {
+        let file_name = match path.file_name() {
+            Some(name) => name.to_string_lossy(),
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "path does not name a file",
+                ))
+            }
+        };
+        let temp_path =
+            path.with_file_name(format!(".{}.prettify-{}.tmp", file_name, process::id()));
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)?;
+        let atomic_file = AtomicFile {
+            path: path.to_path_buf(),
+            temp_path,
+            file: Some(file),
+        };
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(&atomic_file.temp_path, metadata.permissions())?;
+        }
+
+        Ok(atomic_file)
+    }
+
+    /// Renames the file over its destination. On errors the temporary file is removed.
+    pub fn commit(mut self) -> io::Result<()> {
+        // Syncing while the file is still held lets `drop` remove it if that fails.
+        self.file()?.sync_all()?;
+        self.file = None;
+        let result = fs::rename(&self.temp_path, &self.path);
+        if result.is_err() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+        result
+    }
+
+    fn file(&mut self) -> io::Result<&mut File> {
+        match self.file.as_mut() {
+            Some(file) => Ok(file),
+            None => Err(io::Error::other("file was already committed")),
+        }
+    }
+}
+
+impl Write for AtomicFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file()?.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file()?.flush()
+    }
+}
+
+impl Drop for AtomicFile {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            let _ = fs::remove_file(&self.temp_path);
+        }
+    }
+}
+
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let mut file = AtomicFile::create(path)?;
+    file.write_all(contents)?;
+    file.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("prettify-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_write_replaces_contents() {
+        let dir = test_dir("atomic-write");
+        let path = dir.join("config.json");
+
+        write(&path, b"first").unwrap();
+        write(&path, b"second").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_drop_without_commit_keeps_original() {
+        let dir = test_dir("atomic-drop");
+        let path = dir.join("config.json");
+        fs::write(&path, b"original").unwrap();
+
+        {
+            let mut file = AtomicFile::create(&path).unwrap();
+            file.write_all(b"partial").unwrap();
+        }
+
+        assert_eq!(fs::read(&path).unwrap(), b"original");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_failed_commit_removes_temp_file() {
+        let dir = test_dir("atomic-failed");
+        let path = dir.join("taken");
+        fs::create_dir(&path).unwrap();
+        fs::write(path.join("inside.json"), b"{}").unwrap();
+
+        assert!(write(&path, b"formatted").is_err());
+
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        assert_eq!(fs::read(path.join("inside.json")).unwrap(), b"{}");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_keeps_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = test_dir("atomic-permissions");
+        let path = dir.join("script.sh");
+        fs::write(&path, b"original").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o750)).unwrap();
+
+        write(&path, b"formatted").unwrap();
+
+        assert_eq!(
+            fs::metadata(&path).unwrap().permissions().mode() & 0o777,
+            0o750
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,79 @@
+use std::env;
+
+/// When to color the output, chosen with `--color`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ColorChoice {
+    /// Colors when writing to a terminal, unless `NO_COLOR` is set. `CLICOLOR_FORCE` colors even
+    /// when not writing to a terminal.
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorChoice {
+    pub fn from_name(name: &str) -> Option<ColorChoice> {
+        match name.to_lowercase().as_str() {
+            "auto" => Some(ColorChoice::Auto),
+            "always" => Some(ColorChoice::Always),
+            "never" => Some(ColorChoice::Never),
+            _ => None,
+        }
+    }
+
+    pub fn should_color(self, is_terminal: bool) -> bool {
+        self.should_color_with(
+            env::var("NO_COLOR").ok().as_deref(),
+            env::var("CLICOLOR_FORCE").ok().as_deref(),
+            is_terminal,
+        )
+    }
+
+    fn should_color_with(
+        self,
+        no_color: Option<&str>,
+        clicolor_force: Option<&str>,
+        is_terminal: bool,
+    ) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => {
+                if no_color.is_some_and(|v| !v.is_empty()) {
+                    false
+                } else if clicolor_force.is_some_and(|v| !v.is_empty() && v != "0") {
+                    true
+                } else {
+                    is_terminal
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_name() {
+        assert_eq!(ColorChoice::from_name("auto"), Some(ColorChoice::Auto));
+        assert_eq!(ColorChoice::from_name("Always"), Some(ColorChoice::Always));
+        assert_eq!(ColorChoice::from_name("never"), Some(ColorChoice::Never));
+        assert_eq!(ColorChoice::from_name("sometimes"), None);
+    }
+
+    #[test]
+    fn test_should_color() {
+        let auto = ColorChoice::Auto;
+        assert!(auto.should_color_with(None, None, true));
+        assert!(!auto.should_color_with(None, None, false));
+        assert!(!auto.should_color_with(Some("1"), None, true));
+        assert!(auto.should_color_with(Some(""), None, true));
+        assert!(auto.should_color_with(None, Some("1"), false));
+        assert!(!auto.should_color_with(None, Some("0"), false));
+        assert!(!auto.should_color_with(Some("1"), Some("1"), true));
+
+        assert!(ColorChoice::Always.should_color_with(Some("1"), None, false));
+        assert!(!ColorChoice::Never.should_color_with(None, Some("1"), true));
+    }
+}
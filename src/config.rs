@@ -0,0 +1,474 @@
+use crate::converters;
+use crate::error::Error;
+use crate::options::Options;
+use crate::theme::{self, Theme};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Value;
+
+pub const PROJECT_CONFIG_NAME: &str = ".prettifyrc";
+const ALL_CONVERTERS_TABLE: &str = "all";
+
+/// Settings read from configuration files. The top level holds general settings, the `[all]`
+/// table options for every converter and a table named after a converter, like `[json]`,
+/// options for only that converter. Color themes are defined in `[themes.NAME]` tables.
+#[derive(Debug, PartialEq, Default)]
+pub struct Config {
+    pub input_type: Option<String>,
+    pub verbose: Option<bool>,
+    pub theme: Option<String>,
+    /// Styles by token kind for each user defined theme, with `base` naming the theme to start
+    /// from.
+    pub themes: HashMap<String, HashMap<String, String>>,
+    pub shared: HashMap<String, String>,
+    pub per_converter: HashMap<String, HashMap<String, String>>,
+    /// Files the settings were read from, in the order they were applied.
+    pub sources: Vec<PathBuf>,
+}
+
+impl Config {
+    /// Reads the user config from `$XDG_CONFIG_HOME/prettify/config.toml` and then the closest
+    /// `.prettifyrc` in the current directory or one of its parents, which takes precedence.
+    pub fn load() -> Result<Config, String> {
+        let mut config = Config::default();
+        let project_config = env::current_dir()
+            .ok()
+            .and_then(|dir| find_project_config(&dir));
+        for path in user_config_path().into_iter().chain(project_config) {
+            if path.is_file() {
+                config.merge(Config::read(&path)?);
+            }
+        }
+
+        Ok(config)
+    }
+
+    pub fn read(path: &Path) -> Result<Config, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read '{}': {}", path.display(), e))?;
+        let mut config = Config::parse(&text)
+            .map_err(|e| format!("Config file '{}' is not valid: {}", path.display(), e))?;
+        config.sources.push(path.to_path_buf());
+        Ok(config)
+    }
+
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let table = match text.parse::<Value>().map_err(|e| e.to_string())? {
+            Value::Table(table) => table,
+            _ => return Err(String::from("expected a table")),
+        };
+
+        let mut config = Config::default();
+        for (key, value) in table {
+            match (key.as_str(), value) {
+                ("type", Value::String(input_type)) => config.input_type = Some(input_type),
+                ("verbose", Value::Boolean(verbose)) => config.verbose = Some(verbose),
+                ("theme", Value::String(theme)) => config.theme = Some(theme),
+                ("themes", Value::Table(themes)) => {
+                    for (name, styles) in themes {
+                        let styles = match styles {
+                            Value::Table(styles) => styles,
+                            other => {
+                                return Err(format!(
+                                    "'themes.{}' can not be {}",
+                                    name,
+                                    other.type_str()
+                                ))
+                            }
+                        };
+                        let table_name = format!("themes.{}", name);
+                        config
+                            .themes
+                            .insert(name, option_values(&table_name, styles)?);
+                    }
+                }
+                (ALL_CONVERTERS_TABLE, Value::Table(options)) => {
+                    config.shared = option_values(&key, options)?;
+                    if let Some(option) = config.shared.keys().find(|o| !is_known_option(o)) {
+                        return Err(format!("'{}' is not an option of any converter", option));
+                    }
+                }
+                (converter, Value::Table(options)) if is_converter(converter) => {
+                    config
+                        .per_converter
+                        .insert(converter.to_lowercase(), option_values(&key, options)?);
+                }
+                ("type" | "verbose" | "theme" | "themes", value) => {
+                    return Err(format!("'{}' can not be {}", key, value.type_str()))
+                }
+                _ => return Err(format!("unknown setting '{}'", key)),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Overlays another config on top of this one.
+    pub fn merge(&mut self, other: Config) {
+        if other.input_type.is_some() {
+            self.input_type = other.input_type;
+        }
+        if other.verbose.is_some() {
+            self.verbose = other.verbose;
+        }
+        if other.theme.is_some() {
+            self.theme = other.theme;
+        }
+        self.themes.extend(other.themes);
+        for (name, value) in other.shared {
+            self.set_for_all(&name, value);
+        }
+        for (converter, options) in other.per_converter {
+            self.per_converter
+                .entry(converter)
+                .or_default()
+                .extend(options);
+        }
+        self.sources.extend(other.sources);
+    }
+
+    pub fn apply(&self, options: &mut Options) {
+        for (name, value) in &self.shared {
+            options.set_for_all(name, value);
+        }
+        for (converter, converter_options) in &self.per_converter {
+            options
+                .per_converter
+                .entry(converter.clone())
+                .or_default()
+                .extend(converter_options.clone());
+        }
+    }
+
+    /// Finds a theme defined in the config, or else a built in one.
+    pub fn resolve_theme(&self, name: &str) -> Result<Theme, String> {
+        self.resolve_theme_from(name, &mut Vec::new())
+    }
+
+    /// Resolves a theme with the names of the themes based on it, to catch a theme that is based on
+    /// itself through others.
+    fn resolve_theme_from<'a>(
+        &'a self,
+        name: &'a str,
+        based_on_it: &mut Vec<&'a str>,
+    ) -> Result<Theme, String> {
+        let styles = match self.themes.get(name) {
+            Some(styles) => styles,
+            None => {
+                return Theme::built_in(name).ok_or_else(|| {
+                    format!(
+                        "'{}' is not a theme. The built in themes are {}.",
+                        name,
+                        theme::BUILT_IN_THEMES.join(", ")
+                    )
+                })
+            }
+        };
+
+        based_on_it.push(name);
+        let base = match styles.get("base") {
+            Some(base) if based_on_it.contains(&base.as_str()) => {
+                let cycle_start = based_on_it.iter().position(|n| n == base).unwrap();
+                let mut cycle = based_on_it[cycle_start..].to_vec();
+                cycle.push(base);
+                return Err(Error::InvalidOption {
+                    option: format!("themes.{}.base", name),
+                    value: base.clone(),
+                    expected: format!(
+                        "a theme not based on '{}', as {} is a cycle",
+                        name,
+                        cycle.join(" -> ")
+                    ),
+                }
+                .to_string());
+            }
+            Some(base) => self.resolve_theme_from(base, based_on_it)?,
+            None => Theme::default(),
+        };
+        let mut styles = styles.clone();
+        styles.remove("base");
+        Theme::custom(name, &base, &styles).map_err(|e| format!("theme '{}': {}", name, e))
+    }
+
+    fn set_for_all(&mut self, name: &str, value: String) {
+        for options in self.per_converter.values_mut() {
+            options.remove(name);
+        }
+        self.shared.insert(String::from(name), value);
+    }
+}
+
+/// Formats the settings as a config file, listing every option of every converter with the value
+/// that would be used.
+pub fn to_toml(options: &Options) -> String {
+    let mut toml = format!(
+        "type = {}\nverbose = {}\ntheme = {}\n",
+        Value::String(options.input_type.clone()),
+        options.verbose,
+        Value::String(options.theme.name.clone())
+    );
+    for converter in converters::get_converters() {
+        toml.push_str(&format!("\n[{}]\n", converter.name().to_lowercase()));
+        let converter_options = options.converter_options(converter.name());
+        for schema in converter.options() {
+            let value = converter_options
+                .get(schema.name)
+                .map(String::as_str)
+                .or(schema.default);
+            match value {
+                Some(value) => toml.push_str(&format!("{} = {}\n", schema.name, toml_value(value))),
+                None => toml.push_str(&format!("# {} is not set\n", schema.name)),
+            }
+        }
+    }
+
+    toml
+}
+
+fn user_config_path() -> Option<PathBuf> {
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_home.join("prettify").join("config.toml"))
+}
+
+fn find_project_config(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .map(|dir| dir.join(PROJECT_CONFIG_NAME))
+        .find(|path| path.is_file())
+}
+
+fn option_values(
+    table_name: &str,
+    options: toml::value::Table,
+) -> Result<HashMap<String, String>, String> {
+    let mut values = HashMap::new();
+    for (name, value) in options {
+        let value = match value {
+            Value::String(s) => s,
+            Value::Integer(i) => i.to_string(),
+            Value::Boolean(b) => b.to_string(),
+            other => {
+                return Err(format!(
+                    "'{}.{}' can not be {}",
+                    table_name,
+                    name,
+                    other.type_str()
+                ))
+            }
+        };
+        values.insert(name, value);
+    }
+
+    Ok(values)
+}
+
+fn is_converter(name: &str) -> bool {
+    converters::get_converters()
+        .iter()
+        .any(|c| c.name().eq_ignore_ascii_case(name))
+}
+
+fn is_known_option(name: &str) -> bool {
+    converters::get_converters()
+        .iter()
+        .any(|c| c.options().iter().any(|schema| schema.name == name))
+}
+
+fn toml_value(value: &str) -> String {
+    if value == "true" || value == "false" || value.parse::<i64>().is_ok() {
+        String::from(value)
+    } else {
+        Value::String(String::from(value)).to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::TokenKind;
+    use colored_json::Color;
+
+    fn map(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (String::from(*k), String::from(*v)))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let config = Config::parse(
+            r#"
+type = "json"
+
+[all]
+color = false
+indent = 4
+
+[JSON]
+indent = 2
+
+[csv]
+separator = ";"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.input_type, Some(String::from("json")));
+        assert_eq!(config.verbose, None);
+        assert_eq!(config.shared, map(&[("color", "false"), ("indent", "4")]));
+        assert_eq!(config.per_converter["json"], map(&[("indent", "2")]));
+        assert_eq!(config.per_converter["csv"], map(&[("separator", ";")]));
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        assert!(Config::parse("type = ").is_err());
+        assert_eq!(
+            Config::parse("colour = true"),
+            Err(String::from("unknown setting 'colour'"))
+        );
+        assert_eq!(
+            Config::parse("verbose = \"yes\""),
+            Err(String::from("'verbose' can not be string"))
+        );
+        assert_eq!(
+            Config::parse("[all]\nindnet = 2"),
+            Err(String::from("'indnet' is not an option of any converter"))
+        );
+        assert_eq!(
+            Config::parse("[json]\nindent = [2]"),
+            Err(String::from("'json.indent' can not be array"))
+        );
+    }
+
+    #[test]
+    fn test_merge_and_apply() {
+        let mut config = Config::parse("[all]\nindent = 3\n[json]\nindent = 2").unwrap();
+        config.merge(Config::parse("type = \"xml\"\n[all]\nindent = 8\ncolor = false").unwrap());
+        assert_eq!(config.input_type, Some(String::from("xml")));
+
+        let mut options = Options::default();
+        options.set_for_all("indent", "4");
+        options.set_for_all("sort_keys", "true");
+        config.apply(&mut options);
+
+        let json_options = options.converter_options("JSON");
+        assert_eq!(json_options.get("indent"), Some(&String::from("8")));
+        assert_eq!(json_options.get("color"), Some(&String::from("false")));
+        assert_eq!(json_options.get("sort_keys"), Some(&String::from("true")));
+    }
+
+    #[test]
+    fn test_themes() {
+        let config = Config::parse(
+            r#"
+theme = "solarized"
+
+[themes.solarized]
+base = "dark"
+key = "bold yellow"
+
+[themes.loud]
+base = "solarized"
+string = "bright-green on black"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.theme, Some(String::from("solarized")));
+
+        let solarized = config.resolve_theme("solarized").unwrap();
+        assert_eq!(solarized.name, "solarized");
+        assert_eq!(
+            solarized.paint(TokenKind::Key, "a"),
+            "\u{1b}[1;33ma\u{1b}[0m"
+        );
+        assert_eq!(solarized.style(TokenKind::Number), Color::Yellow.normal());
+
+        let loud = config.resolve_theme("loud").unwrap();
+        assert_eq!(loud.style(TokenKind::Key), Color::Yellow.bold());
+        assert_eq!(
+            config.resolve_theme("light"),
+            Ok(Theme::built_in("light").unwrap())
+        );
+        assert!(config.resolve_theme("solarised").is_err());
+
+        let config = Config::parse(
+            "[themes.bad]
+keys = \"red\"",
+        )
+        .unwrap();
+        assert!(config.resolve_theme("bad").is_err());
+        let config = Config::parse(
+            "[themes.loop]
+base = \"loop\"",
+        )
+        .unwrap();
+        assert!(config.resolve_theme("loop").is_err());
+        let config = Config::parse(
+            "[themes.a]
+base = \"b\"
+[themes.b]
+base = \"a\"
+[themes.c]
+base = \"a\"",
+        )
+        .unwrap();
+        assert_eq!(
+            config.resolve_theme("c"),
+            Err(Error::InvalidOption {
+                option: String::from("themes.b.base"),
+                value: String::from("a"),
+                expected: String::from("a theme not based on 'b', as a -> b -> a is a cycle"),
+            }
+            .to_string())
+        );
+    }
+
+    #[test]
+    fn test_find_project_config() {
+        let dir = env::temp_dir().join(format!("prettify-config-{}", std::process::id()));
+        let nested = dir.join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(dir.join(PROJECT_CONFIG_NAME), "[all]\nindent = 2").unwrap();
+
+        assert_eq!(
+            find_project_config(&nested),
+            Some(dir.join(PROJECT_CONFIG_NAME))
+        );
+        assert_eq!(
+            Config::read(&dir.join(PROJECT_CONFIG_NAME)).unwrap().shared,
+            map(&[("indent", "2")])
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_to_toml() {
+        let mut options = Options::default();
+        options.set_for_all("indent", "4");
+        let mut json = HashMap::new();
+        json.insert(String::from("indent"), String::from("2"));
+        options.per_converter.insert(String::from("json"), json);
+
+        let toml = to_toml(&options);
+        assert!(toml.starts_with(
+            "type = \"all\"\nverbose = false\ntheme = \"default\"\n\n[json]\nindent = 2\n"
+        ));
+        assert!(toml.contains(
+            "\n[xml]\nindent = 4\nindent_style = \"space\"\ncolor = true\nend_of_line = \"lf\"\n# insert_final_newline is not set\n"
+        ));
+        assert!(toml.contains("\n[csv]\n# separator is not set\nspacing = 1\n"));
+        assert!(toml.contains("alphabet = \"standard\"\n"));
+
+        let reparsed = Config::parse(&toml).unwrap();
+        assert_eq!(
+            reparsed.per_converter["json"].get("indent"),
+            Some(&String::from("2"))
+        );
+    }
+}
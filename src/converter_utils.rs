@@ -1,15 +1,40 @@
+use crate::error::Error;
+use crate::options::{OptionSchema, OptionType};
+use crate::text;
+use std::borrow::Cow;
 use std::collections::HashMap;
 
-pub fn extract_u8(name: &str, converter_options: &HashMap<String, String>, default: u8) -> u8 {
-    let option_value = converter_options.get(name);
+pub const INDENT_STYLE_OPTION_NAME: &str = "indent_style";
+pub const END_OF_LINE_OPTION_NAME: &str = "end_of_line";
+pub const INSERT_FINAL_NEWLINE_OPTION_NAME: &str = "insert_final_newline";
+
+pub const INDENT_STYLE_OPTION: OptionSchema = OptionSchema {
+    name: INDENT_STYLE_OPTION_NAME,
+    option_type: OptionType::Choice(&["space", "tab"]),
+    default: Some("space"),
+    help: "Indents with spaces, or with one tab for each level.",
+};
+pub const END_OF_LINE_OPTION: OptionSchema = OptionSchema {
+    name: END_OF_LINE_OPTION_NAME,
+    option_type: OptionType::Choice(&["lf", "crlf", "cr"]),
+    default: Some("lf"),
+    help: "Line endings to use in the output.",
+};
+/// Unset keeps the output as the converter writes it.
+pub const INSERT_FINAL_NEWLINE_OPTION: OptionSchema = OptionSchema {
+    name: INSERT_FINAL_NEWLINE_OPTION_NAME,
+    option_type: OptionType::Bool,
+    default: None,
+    help: "Ends the output with a line ending when true, or makes sure it does not end with one when false.",
+};
 
-    if option_value == None {
-        default
-    } else {
-        match option_value.unwrap().parse::<u8>() {
+pub fn extract_u8(name: &str, converter_options: &HashMap<String, String>, default: u8) -> u8 {
+    match converter_options.get(name) {
+        Some(value) => match value.parse::<u8>() {
             Ok(i) => i,
             Err(_) => default,
-        }
+        },
+        None => default,
     }
 }
 
@@ -18,16 +43,73 @@ pub fn extract_bool(
     converter_options: &HashMap<String, String>,
     default: bool,
 ) -> bool {
-    let option_value = converter_options.get(name);
-
-    if option_value == None {
-        default
-    } else if option_value.unwrap().eq_ignore_ascii_case("true") {
-        true
-    } else if option_value.unwrap().eq_ignore_ascii_case("false") {
-        false
-    } else {
-        default
+    match converter_options.get(name) {
+        Some(value) if value.eq_ignore_ascii_case("true") => true,
+        Some(value) if value.eq_ignore_ascii_case("false") => false,
+        _ => default,
+    }
+}
+
+pub fn extract_char(
+    name: &str,
+    converter_options: &HashMap<String, String>,
+    default: Option<char>,
+) -> Option<char> {
+    match converter_options.get(name) {
+        Some(value) if value.eq_ignore_ascii_case("tab") || value == "\\t" => Some('\t'),
+        Some(value) if value.chars().count() == 1 => value.chars().next(),
+        _ => default,
+    }
+}
+
+/// Indentation for one level: a tab when `indent_style` is `tab`, or else `size` spaces.
+pub fn extract_indent(converter_options: &HashMap<String, String>, size: u8) -> String {
+    match converter_options.get(INDENT_STYLE_OPTION_NAME) {
+        Some(style) if style.eq_ignore_ascii_case("tab") => String::from("\t"),
+        _ => " ".repeat(size.into()),
+    }
+}
+
+pub fn extract_newline(converter_options: &HashMap<String, String>) -> &'static str {
+    match converter_options.get(END_OF_LINE_OPTION_NAME) {
+        Some(value) if value.eq_ignore_ascii_case("crlf") => "\r\n",
+        Some(value) if value.eq_ignore_ascii_case("cr") => "\r",
+        _ => "\n",
+    }
+}
+
+/// Adds or removes the final line ending of converted text for `insert_final_newline`. When that is
+/// not set, the output ends with a line ending if the input did.
+pub fn apply_final_newline(
+    mut output: String,
+    converter_options: &HashMap<String, String>,
+    input_has_final_newline: bool,
+) -> String {
+    let newline = extract_newline(converter_options);
+    let final_newline = converter_options
+        .get(INSERT_FINAL_NEWLINE_OPTION_NAME)
+        .and_then(|value| value.to_lowercase().parse::<bool>().ok());
+    match final_newline.unwrap_or(input_has_final_newline) {
+        true if !output.is_empty() && !output.ends_with(['\n', '\r']) => {
+            output.push_str(newline);
+        }
+        false if final_newline.is_some() => {
+            output.truncate(output.trim_end_matches(['\n', '\r']).len())
+        }
+        _ => {}
+    }
+    output
+}
+
+pub fn decode_text<'a>(converter: &str, input: &'a [u8]) -> Result<Cow<'a, str>, Error> {
+    text::decode(input)
+        .ok_or_else(|| Error::cannot_convert(converter, "input is not valid UTF-8 or UTF-16 text"))
+}
+
+pub fn strip_location(message: &str) -> &str {
+    match message.rfind(" at line ") {
+        Some(i) => &message[..i],
+        None => message,
     }
 }
 
@@ -50,18 +132,18 @@ mod tests {
         options.insert(String::from("invalid4"), String::from("57"));
         options.insert(String::from("empty"), String::from(""));
 
-        assert_eq!(extract_bool("valid_true1", &options, false), true);
-        assert_eq!(extract_bool("valid_true2", &options, false), true);
-        assert_eq!(extract_bool("valid_true3", &options, false), true);
-        assert_eq!(extract_bool("valid_false1", &options, true), false);
-        assert_eq!(extract_bool("valid_false2", &options, true), false);
-        assert_eq!(extract_bool("valid_false3", &options, true), false);
-        assert_eq!(extract_bool("invalid1", &options, false), false);
-        assert_eq!(extract_bool("invalid2", &options, false), false);
-        assert_eq!(extract_bool("invalid3", &options, true), true);
-        assert_eq!(extract_bool("invalid4", &options, false), false);
-        assert_eq!(extract_bool("empty", &options, true), true);
-        assert_eq!(extract_bool("not_present", &options, true), true);
+        assert!(extract_bool("valid_true1", &options, false));
+        assert!(extract_bool("valid_true2", &options, false));
+        assert!(extract_bool("valid_true3", &options, false));
+        assert!(!extract_bool("valid_false1", &options, true));
+        assert!(!extract_bool("valid_false2", &options, true));
+        assert!(!extract_bool("valid_false3", &options, true));
+        assert!(!extract_bool("invalid1", &options, false));
+        assert!(!extract_bool("invalid2", &options, false));
+        assert!(extract_bool("invalid3", &options, true));
+        assert!(!extract_bool("invalid4", &options, false));
+        assert!(extract_bool("empty", &options, true));
+        assert!(extract_bool("not_present", &options, true));
     }
 
     #[test]
@@ -87,4 +169,76 @@ mod tests {
         assert_eq!(extract_u8("too_big", &options, 26), 26);
         assert_eq!(extract_u8("too_small", &options, 0), 0);
     }
+
+    #[test]
+    fn test_extract_char() {
+        let mut options = HashMap::new();
+        options.insert(String::from("comma"), String::from(","));
+        options.insert(String::from("tab_name"), String::from("TAB"));
+        options.insert(String::from("tab_escape"), String::from("\\t"));
+        options.insert(String::from("tab_literal"), String::from("\t"));
+        options.insert(String::from("unicode"), String::from("¦"));
+        options.insert(String::from("too_long"), String::from(";;"));
+        options.insert(String::from("empty"), String::from(""));
+
+        assert_eq!(extract_char("comma", &options, None), Some(','));
+        assert_eq!(extract_char("tab_name", &options, None), Some('\t'));
+        assert_eq!(extract_char("tab_escape", &options, None), Some('\t'));
+        assert_eq!(extract_char("tab_literal", &options, None), Some('\t'));
+        assert_eq!(extract_char("unicode", &options, None), Some('¦'));
+        assert_eq!(extract_char("too_long", &options, Some('|')), Some('|'));
+        assert_eq!(extract_char("empty", &options, None), None);
+        assert_eq!(extract_char("not_present", &options, Some(',')), Some(','));
+    }
+
+    #[test]
+    fn test_extract_indent() {
+        let mut options = HashMap::new();
+        assert_eq!(extract_indent(&options, 2), "  ");
+        options.insert(String::from("indent_style"), String::from("Tab"));
+        assert_eq!(extract_indent(&options, 2), "\t");
+        options.insert(String::from("indent_style"), String::from("space"));
+        assert_eq!(extract_indent(&options, 0), "");
+    }
+
+    #[test]
+    fn test_apply_final_newline() {
+        let mut options = HashMap::new();
+        assert_eq!(
+            apply_final_newline(String::from("a\nb"), &options, false),
+            "a\nb"
+        );
+        assert_eq!(
+            apply_final_newline(String::from("a\nb"), &options, true),
+            "a\nb\n"
+        );
+
+        options.insert(String::from("end_of_line"), String::from("crlf"));
+        assert_eq!(
+            apply_final_newline(String::from("a\r\nb"), &options, true),
+            "a\r\nb\r\n"
+        );
+
+        options.insert(String::from("insert_final_newline"), String::from("true"));
+        assert_eq!(
+            apply_final_newline(String::from("a\r\nb"), &options, false),
+            "a\r\nb\r\n"
+        );
+        assert_eq!(apply_final_newline(String::new(), &options, false), "");
+
+        options.insert(String::from("insert_final_newline"), String::from("false"));
+        assert_eq!(
+            apply_final_newline(String::from("a\r\nb\r\n"), &options, true),
+            "a\r\nb"
+        );
+    }
+
+    #[test]
+    fn test_strip_location() {
+        assert_eq!(
+            strip_location("expected value at line 1 column 2"),
+            "expected value"
+        );
+        assert_eq!(strip_location("no location"), "no location");
+    }
 }
@@ -1,18 +1,31 @@
 pub mod base64_converter;
+pub mod csv_converter;
 pub mod json_converter;
+mod json_stream;
+pub mod xml_converter;
+pub mod yaml_converter;
 
 use crate::error::Error;
-use crate::options::Options;
+use crate::options::{OptionSchema, Options};
 
-pub trait Converter {
+pub trait Converter: Send + Sync {
     fn name(&self) -> &str;
-    fn options(&self) -> Vec<&str>;
-    fn prettify(&self, input: &str, options: &Options) -> Result<String, Error>;
+    fn options(&self) -> Vec<OptionSchema>;
+    /// How confident the converter is that the input is in its format, from 0 (not at all) to 100.
+    fn detect(&self, input: &[u8]) -> u8;
+    fn prettify(&self, input: &[u8], options: &Options) -> Result<String, Error>;
+
+    fn prettify_bytes(&self, input: &[u8], options: &Options) -> Result<Vec<u8>, Error> {
+        self.prettify(input, options).map(String::into_bytes)
+    }
 }
 
 pub fn get_converters() -> Vec<Box<dyn Converter>> {
     vec![
         Box::new(json_converter::JsonConverter::new()),
+        Box::new(xml_converter::XmlConverter::new()),
+        Box::new(yaml_converter::YamlConverter::new()),
+        Box::new(csv_converter::CsvConverter::new()),
         Box::new(base64_converter::Base64Converter::new()),
     ]
 }
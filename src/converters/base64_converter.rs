@@ -1,9 +1,38 @@
+use crate::converter_utils;
 use crate::converters::Converter;
-use crate::error::Error;
-use crate::options::Options;
-use base64::{decode, encode};
+use crate::error::{Error, Location};
+use crate::options::{OptionSchema, OptionType, Options};
+use crate::text;
+use base64::{decode_config, encode_config, CharacterSet, Config, DecodeError, STANDARD, URL_SAFE};
+use std::collections::HashMap;
 use std::str;
 
+const CONVERTER_NAME: &str = "Base64";
+const ALPHABET_OPTION_NAME: &str = "alphabet";
+const PADDING_OPTION_NAME: &str = "padding";
+const WRAP_OPTION_NAME: &str = "wrap";
+
+const ALPHABET_OPTION: OptionSchema = OptionSchema {
+    name: ALPHABET_OPTION_NAME,
+    option_type: OptionType::Choice(&["standard", "url"]),
+    default: Some("standard"),
+    help: "Alphabet to encode with. Decoding detects the alphabet on its own.",
+};
+const PADDING_OPTION: OptionSchema = OptionSchema {
+    name: PADDING_OPTION_NAME,
+    option_type: OptionType::Bool,
+    default: Some("true"),
+    help: "Adds the trailing '=' padding when encoding.",
+};
+const WRAP_OPTION: OptionSchema = OptionSchema {
+    name: WRAP_OPTION_NAME,
+    option_type: OptionType::Integer { min: 0, max: 255 },
+    default: Some("0"),
+    help:
+        "Breaks encoded output into lines of this many characters, 76 for MIME, or 0 to not wrap.",
+};
+
+#[derive(Default)]
 pub struct Base64Converter {}
 
 impl Base64Converter {
@@ -14,60 +43,229 @@ impl Base64Converter {
 
 impl Converter for Base64Converter {
     fn name(&self) -> &str {
-        "Base64"
+        CONVERTER_NAME
     }
 
-    fn options(&self) -> Vec<&str> {
-        vec![]
+    fn options(&self) -> Vec<OptionSchema> {
+        vec![ALPHABET_OPTION, PADDING_OPTION, WRAP_OPTION]
     }
 
-    fn prettify(&self, input: &str, options: &Options) -> Result<String, Error> {
+    fn detect(&self, input: &[u8]) -> u8 {
+        let input = match text::decode(input) {
+            Some(input) => input,
+            None => return 0,
+        };
+
+        let decoded = match decode_any(&input).map(String::from_utf8) {
+            Ok(Ok(decoded)) if !decoded.is_empty() => decoded,
+            Ok(Err(e)) if sniff_file_type(e.as_bytes()).is_some() => return 50,
+            _ => return 0,
+        };
+
+        if decoded
+            .chars()
+            .any(|c| c.is_control() && !c.is_whitespace())
+        {
+            0
+        } else if input.trim().len() < 8 {
+            30
+        } else {
+            60
+        }
+    }
+
+    fn prettify(&self, input: &[u8], options: &Options) -> Result<String, Error> {
         if options.reverse {
             if options.input_type.eq_ignore_ascii_case(self.name()) {
-                Ok(encode(input))
+                let base64_options = Base64ConverterOptions::from_options(
+                    &options.converter_options(CONVERTER_NAME),
+                );
+                Ok(encode(input, &base64_options))
             } else {
                 if options.verbose {
                     println!("You can only encode to base64 if you specify it as the converter directly.");
                 }
-                Err(Error::CannotConvert)
+                Err(Error::cannot_convert(
+                    CONVERTER_NAME,
+                    "encoding is only done when Base64 is selected as the type",
+                ))
+            }
+        } else {
+            let input = converter_utils::decode_text(CONVERTER_NAME, input)?;
+            let decoded = decode_any(&input)?;
+            match String::from_utf8(decoded) {
+                Ok(string) => Ok(string),
+                Err(e) => Ok(hexdump(e.as_bytes())),
             }
+        }
+    }
+
+    fn prettify_bytes(&self, input: &[u8], options: &Options) -> Result<Vec<u8>, Error> {
+        if options.reverse {
+            self.prettify(input, options).map(String::into_bytes)
         } else {
-            match decode(input) {
-                Ok(decoded) => u8s_to_string(decoded),
-                Err(_) => Err(Error::CannotConvert),
+            decode_any(&converter_utils::decode_text(CONVERTER_NAME, input)?)
+        }
+    }
+}
+
+/// Decodes standard and URL-safe Base64, with or without padding, ignoring line breaks and other whitespace.
+fn decode_any(input: &str) -> Result<Vec<u8>, Error> {
+    let (compact, offsets): (String, Vec<usize>) = input
+        .char_indices()
+        .filter(|(_, c)| !c.is_ascii_whitespace())
+        .map(|(i, c)| (c, i))
+        .unzip();
+
+    let config = if compact.contains(['-', '_']) {
+        URL_SAFE
+    } else {
+        STANDARD
+    };
+
+    decode_config(&compact, config).map_err(|e| decode_error(input, &offsets, e))
+}
+
+fn decode_error(input: &str, offsets: &[usize], error: DecodeError) -> Error {
+    let (message, offset) = match error {
+        DecodeError::InvalidByte(offset, byte) => {
+            (format!("invalid character '{}'", byte as char), offset)
+        }
+        DecodeError::InvalidLastSymbol(offset, byte) => (
+            format!("invalid final character '{}'", byte as char),
+            offset,
+        ),
+        DecodeError::InvalidLength => {
+            return Error::cannot_convert(CONVERTER_NAME, "input has an invalid length");
+        }
+    };
+
+    Error::cannot_convert_at(
+        CONVERTER_NAME,
+        &message,
+        Location::from_offset(input, offsets.get(offset).copied().unwrap_or(input.len())),
+    )
+}
+
+fn encode(input: &[u8], options: &Base64ConverterOptions) -> String {
+    let encoded = encode_config(input, Config::new(options.alphabet, options.padding));
+    if options.wrap == 0 {
+        return encoded;
+    }
+
+    encoded
+        .as_bytes()
+        .chunks(usize::from(options.wrap))
+        .map(|line| str::from_utf8(line).unwrap())
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+fn sniff_file_type(bytes: &[u8]) -> Option<&'static str> {
+    const MAGIC_NUMBERS: [(&[u8], &str); 6] = [
+        (b"\x89PNG\r\n\x1a\n", "PNG image"),
+        (b"\x1f\x8b", "gzip compressed data"),
+        (b"PK\x03\x04", "zip archive"),
+        (b"PK\x05\x06", "zip archive"),
+        (b"PK\x07\x08", "zip archive"),
+        (b"%PDF-", "PDF document"),
+    ];
+
+    MAGIC_NUMBERS
+        .iter()
+        .find(|(magic, _)| bytes.starts_with(magic))
+        .map(|(_, name)| *name)
+}
+
+fn hexdump(bytes: &[u8]) -> String {
+    let mut lines = vec![format!(
+        "{}, {} bytes",
+        sniff_file_type(bytes).unwrap_or("Binary data"),
+        bytes.len()
+    )];
+
+    for (i, chunk) in bytes.chunks(16).enumerate() {
+        let mut line = format!("{:08x} ", i * 16);
+        for j in 0..16 {
+            if j == 8 {
+                line.push(' ');
+            }
+            match chunk.get(j) {
+                Some(byte) => line.push_str(&format!(" {:02x}", byte)),
+                None => line.push_str("   "),
             }
         }
+
+        let ascii: String = chunk
+            .iter()
+            .map(|b| {
+                if b.is_ascii_graphic() || *b == b' ' {
+                    *b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        line.push_str(&format!("  |{}|", ascii));
+        lines.push(line);
+    }
+
+    lines.join("\n")
+}
+
+struct Base64ConverterOptions {
+    alphabet: CharacterSet,
+    padding: bool,
+    wrap: u8,
+}
+
+impl Default for Base64ConverterOptions {
+    fn default() -> Base64ConverterOptions {
+        Base64ConverterOptions {
+            alphabet: CharacterSet::Standard,
+            padding: true,
+            wrap: 0,
+        }
     }
 }
 
-fn u8s_to_string(input: Vec<u8>) -> Result<String, Error> {
-    match String::from_utf8(input) {
-        Ok(string) => Ok(string),
-        Err(_) => Err(Error::CannotConvert),
+impl Base64ConverterOptions {
+    pub fn from_options(converter_options: &HashMap<String, String>) -> Base64ConverterOptions {
+        Base64ConverterOptions {
+            alphabet: match converter_options.get(ALPHABET_OPTION_NAME) {
+                Some(value) if value.eq_ignore_ascii_case("url") => CharacterSet::UrlSafe,
+                Some(value) if value.eq_ignore_ascii_case("standard") => CharacterSet::Standard,
+                _ => Base64ConverterOptions::default().alphabet,
+            },
+            padding: converter_utils::extract_bool(
+                PADDING_OPTION_NAME,
+                converter_options,
+                Base64ConverterOptions::default().padding,
+            ),
+            wrap: converter_utils::extract_u8(
+                WRAP_OPTION_NAME,
+                converter_options,
+                Base64ConverterOptions::default().wrap,
+            ),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
 
     #[test]
     fn test_decode_valid() {
         let converter = Base64Converter::new();
-        let options = Options {
-            input_type: String::from("all"),
-            verbose: false,
-            reverse: false,
-            converter_specific: HashMap::new(),
-        };
+        let options = Options::default();
 
         assert_eq!(
-            converter.prettify(&String::from("aGVsbG8gd29ybGQh"), &options),
+            converter.prettify("aGVsbG8gd29ybGQh".as_bytes(), &options),
             Ok(String::from("hello world!"))
         );
         assert_eq!(
-            converter.prettify(&String::from("SGkkQA=="), &options),
+            converter.prettify("SGkkQA==".as_bytes(), &options),
             Ok(String::from("Hi$@"))
         );
     }
@@ -75,25 +273,20 @@ mod tests {
     #[test]
     fn test_decode_invalid() {
         let converter = Base64Converter::new();
-        let options = Options {
-            input_type: String::from("all"),
-            verbose: false,
-            reverse: false,
-            converter_specific: HashMap::new(),
-        };
+        let options = Options::default();
 
-        assert_eq!(
-            converter.prettify(&String::from("aGVsbG8gd29ybGQht"), &options),
-            Err(Error::CannotConvert)
-        );
-        assert_eq!(
-            converter.prettify(&String::from("aGVsbG8gd29ybGQh======="), &options),
-            Err(Error::CannotConvert)
-        );
-        assert_eq!(
-            converter.prettify(&String::from("aGVsbG8gd29ybG?h"), &options),
-            Err(Error::CannotConvert)
-        );
+        assert!(matches!(
+            converter.prettify("aGVsbG8gd29ybGQht".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("aGVsbG8gd29ybGQh=======".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("aGVsbG8gd29ybG?h".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
     }
 
     #[test]
@@ -101,22 +294,21 @@ mod tests {
         let converter = Base64Converter::new();
         let mut options = Options {
             input_type: String::from("base64"),
-            verbose: false,
             reverse: true,
-            converter_specific: HashMap::new(),
+            ..Options::default()
         };
 
         assert_eq!(
-            converter.prettify(&String::from("hello world!"), &options),
+            converter.prettify("hello world!".as_bytes(), &options),
             Ok(String::from("aGVsbG8gd29ybGQh"))
         );
         assert_eq!(
-            converter.prettify(&String::from(""), &options),
+            converter.prettify("".as_bytes(), &options),
             Ok(String::from(""))
         );
         options.input_type = String::from("BASE64");
         assert_eq!(
-            converter.prettify(&String::from("Hi$@"), &options),
+            converter.prettify("Hi$@".as_bytes(), &options),
             Ok(String::from("SGkkQA=="))
         );
     }
@@ -125,20 +317,155 @@ mod tests {
     fn test_encode_invalid_input_type() {
         let converter = Base64Converter::new();
         let mut options = Options {
-            input_type: String::from("all"),
-            verbose: false,
             reverse: true,
-            converter_specific: HashMap::new(),
+            ..Options::default()
         };
 
+        assert!(matches!(
+            converter.prettify("hello world!".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        options.input_type = String::from("JSON");
+        assert!(matches!(
+            converter.prettify("".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+    }
+
+    #[test]
+    fn test_decode_error_location() {
+        let converter = Base64Converter::new();
+        let options = Options::default();
+
         assert_eq!(
-            converter.prettify(&String::from("hello world!"), &options),
-            Err(Error::CannotConvert)
+            converter.prettify("aGVsbG8gd29ybG?h".as_bytes(), &options),
+            Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                "invalid character '?'",
+                Location {
+                    line: 1,
+                    column: 15,
+                    offset: 14
+                }
+            ))
+        );
+        assert_eq!(
+            converter.prettify("aGVsbG8g\nd29ybG?h".as_bytes(), &options),
+            Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                "invalid character '?'",
+                Location {
+                    line: 2,
+                    column: 7,
+                    offset: 15
+                }
+            ))
+        );
+        assert_eq!(
+            converter.prettify("aGVsbG8gd29ybGQht".as_bytes(), &options),
+            Err(Error::cannot_convert(
+                CONVERTER_NAME,
+                "input has an invalid length"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_decode_variants() {
+        let converter = Base64Converter::new();
+        let options = Options::default();
+
+        assert_eq!(
+            converter.prettify("aGVsbG8gd29ybGQh\n".as_bytes(), &options),
+            Ok(String::from("hello world!"))
+        );
+        assert_eq!(
+            converter.prettify("SGk".as_bytes(), &options),
+            Ok(String::from("Hi"))
+        );
+        assert_eq!(
+            converter.prettify("Pz8-Pz8_".as_bytes(), &options),
+            Ok(String::from("??>???"))
         );
-        options.input_type = String::from("JSON");
         assert_eq!(
-            converter.prettify(&String::from(""), &options),
-            Err(Error::CannotConvert)
+            converter.prettify("eyJhbGciOiJIUzI1NiJ9".as_bytes(), &options),
+            Ok(String::from("{\"alg\":\"HS256\"}"))
         );
+        assert_eq!(
+            converter.prettify("aGVsbG8g\r\nd29ybGQh\r\n".as_bytes(), &options),
+            Ok(String::from("hello world!"))
+        );
+    }
+
+    #[test]
+    fn test_encode_options() {
+        let converter = Base64Converter::new();
+        let mut options = Options {
+            input_type: String::from("base64"),
+            reverse: true,
+            ..Options::default()
+        };
+
+        options
+            .converter_specific
+            .insert(String::from(ALPHABET_OPTION_NAME), String::from("url"));
+        options
+            .converter_specific
+            .insert(String::from(PADDING_OPTION_NAME), String::from("false"));
+        assert_eq!(
+            converter.prettify("??>???!".as_bytes(), &options),
+            Ok(String::from("Pz8-Pz8_IQ"))
+        );
+
+        options.converter_specific.clear();
+        options
+            .converter_specific
+            .insert(String::from(WRAP_OPTION_NAME), String::from("8"));
+        assert_eq!(
+            converter.prettify("hello world!".as_bytes(), &options),
+            Ok(String::from("aGVsbG8g\nd29ybGQh"))
+        );
+    }
+
+    #[test]
+    fn test_decode_binary() {
+        let converter = Base64Converter::new();
+        let options = Options {
+            input_type: String::from("base64"),
+            ..Options::default()
+        };
+
+        assert_eq!(
+            converter.prettify("iVBORw0KGgoAAAANSUhEUgAAAAE=".as_bytes(), &options),
+            Ok(String::from(
+                "PNG image, 20 bytes
+00000000  89 50 4e 47 0d 0a 1a 0a  00 00 00 0d 49 48 44 52  |.PNG........IHDR|
+00000010  00 00 00 01                                       |....|"
+            ))
+        );
+        assert_eq!(
+            converter.prettify("/wBBQg==".as_bytes(), &options),
+            Ok(String::from(
+                "Binary data, 4 bytes
+00000000  ff 00 41 42                                       |..AB|"
+            ))
+        );
+        assert_eq!(
+            converter.prettify_bytes("/wBBQg==".as_bytes(), &options),
+            Ok(vec![0xff, 0x00, 0x41, 0x42])
+        );
+    }
+
+    #[test]
+    fn test_detect() {
+        let converter = Base64Converter::new();
+
+        assert_eq!(converter.detect("aGVsbG8gd29ybGQh".as_bytes()), 60);
+        assert_eq!(converter.detect("SGkh".as_bytes()), 30);
+        assert_eq!(converter.detect("AAAA".as_bytes()), 0);
+        assert_eq!(converter.detect("H4sIAAAAAAAA".as_bytes()), 50);
+        assert_eq!(converter.detect("/wBBQg==".as_bytes()), 0);
+        assert_eq!(converter.detect("test".as_bytes()), 0);
+        assert_eq!(converter.detect("".as_bytes()), 0);
     }
 }
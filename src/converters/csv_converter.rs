@@ -0,0 +1,558 @@
+use crate::converter_utils;
+use crate::converters::Converter;
+use crate::error::{Error, Location};
+use crate::options::{OptionSchema, OptionType, Options};
+use crate::text;
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+const CONVERTER_NAME: &str = "CSV";
+const SEPARATOR_OPTION_NAME: &str = "separator";
+const SPACING_OPTION_NAME: &str = "spacing";
+
+const SEPARATOR_OPTION: OptionSchema = OptionSchema {
+    name: SEPARATOR_OPTION_NAME,
+    option_type: OptionType::Char,
+    default: None,
+    help: "Character between columns. Detected from the input when not given.",
+};
+const SPACING_OPTION: OptionSchema = OptionSchema {
+    name: SPACING_OPTION_NAME,
+    option_type: OptionType::Integer { min: 0, max: 16 },
+    default: Some("1"),
+    help: "Number of spaces between aligned columns.",
+};
+
+const SEPARATOR_CANDIDATES: [char; 4] = [',', '\t', ';', '|'];
+
+#[derive(Default)]
+pub struct CsvConverter {}
+
+impl CsvConverter {
+    pub fn new() -> CsvConverter {
+        CsvConverter {}
+    }
+}
+
+impl Converter for CsvConverter {
+    fn name(&self) -> &str {
+        CONVERTER_NAME
+    }
+
+    fn options(&self) -> Vec<OptionSchema> {
+        vec![
+            SEPARATOR_OPTION,
+            SPACING_OPTION,
+            converter_utils::END_OF_LINE_OPTION,
+            converter_utils::INSERT_FINAL_NEWLINE_OPTION,
+        ]
+    }
+
+    fn detect(&self, input: &[u8]) -> u8 {
+        let input = match text::decode(input) {
+            Some(input) => input,
+            None => return 0,
+        };
+
+        match CsvParser::new(&input, detect_separator(&input), true).parse() {
+            Ok(records) if looks_tabular(&records) => 50,
+            _ => 0,
+        }
+    }
+
+    fn prettify(&self, input: &[u8], options: &Options) -> Result<String, Error> {
+        let input = converter_utils::decode_text(CONVERTER_NAME, input)?;
+        let csv_options =
+            CsvConverterOptions::from_options(&options.converter_options(CONVERTER_NAME));
+        let separator = match csv_options.separator {
+            Some(separator) => separator,
+            None => detect_separator(&input),
+        };
+
+        let records = if options.reverse {
+            CsvParser::new(&input, separator, true).parse()?
+        } else {
+            parse_unless_aligned(&input, separator, csv_options.spacing)?
+        };
+        if !options.input_type.eq_ignore_ascii_case(self.name()) && !looks_tabular(&records) {
+            return Err(Error::cannot_convert(
+                CONVERTER_NAME,
+                "needs at least two rows with the same number of columns to guess it is CSV",
+            ));
+        }
+
+        if options.reverse {
+            Ok(format_compact(&records, separator, csv_options.newline))
+        } else {
+            Ok(format_aligned(
+                &records,
+                separator,
+                csv_options.spacing,
+                csv_options.newline,
+            ))
+        }
+    }
+}
+
+fn detect_separator(input: &str) -> char {
+    let first_line = input.lines().next().unwrap_or("");
+    let mut best = SEPARATOR_CANDIDATES[0];
+    let mut best_count = 0;
+    for candidate in SEPARATOR_CANDIDATES.iter() {
+        let count = first_line.matches(*candidate).count();
+        if count > best_count {
+            best = *candidate;
+            best_count = count;
+        }
+    }
+
+    best
+}
+
+/// Spaces around fields are part of them, unless the input was aligned by this converter. The
+/// input is kept when it is already aligned, so formatting it again does not pad it twice.
+fn parse_unless_aligned(
+    input: &str,
+    separator: char,
+    spacing: u8,
+) -> Result<Vec<Vec<String>>, Error> {
+    if let Ok(records) = CsvParser::new(input, separator, true).parse() {
+        if format_aligned(&records, separator, spacing, "\n")
+            .lines()
+            .eq(input.lines())
+        {
+            return Ok(records);
+        }
+    }
+
+    CsvParser::new(input, separator, false).parse()
+}
+
+struct CsvParser<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    separator: char,
+    /// Whether spaces around fields are padding to skip rather than part of the field.
+    strip_padding: bool,
+}
+
+impl<'a> CsvParser<'a> {
+    fn new(input: &'a str, separator: char, strip_padding: bool) -> CsvParser<'a> {
+        CsvParser {
+            input,
+            chars: input.char_indices().peekable(),
+            separator,
+            strip_padding,
+        }
+    }
+
+    fn parse(&mut self) -> Result<Vec<Vec<String>>, Error> {
+        if self.input.trim().is_empty() {
+            return Err(Error::cannot_convert(CONVERTER_NAME, "input is empty"));
+        }
+
+        let mut records = Vec::new();
+        let mut record = Vec::new();
+        loop {
+            self.skip_padding();
+            let (field, quoted) = if self.peek() == Some('"') {
+                (self.parse_quoted_field()?, true)
+            } else {
+                (self.parse_unquoted_field()?, false)
+            };
+            self.skip_padding();
+            let is_blank_line = record.is_empty() && field.is_empty() && !quoted;
+            record.push(field);
+
+            let offset = self.offset();
+            match self.next() {
+                Some(c) if c == self.separator => continue,
+                Some('\r') => {
+                    if self.peek() == Some('\n') {
+                        self.next();
+                    }
+                }
+                Some('\n') | None => (),
+                Some(c) => {
+                    let message = format!("unexpected character '{}' after a quoted field", c);
+                    return Err(self.error_at(&message, offset));
+                }
+            }
+
+            if is_blank_line {
+                record.clear();
+            } else {
+                records.push(record);
+                record = Vec::new();
+            }
+            if self.peek().is_none() {
+                break;
+            }
+        }
+
+        Ok(records)
+    }
+
+    fn parse_quoted_field(&mut self) -> Result<String, Error> {
+        let start = self.offset();
+        self.next();
+
+        let mut field = String::new();
+        loop {
+            match self.next() {
+                Some('"') => {
+                    if self.peek() == Some('"') {
+                        self.next();
+                        field.push('"');
+                    } else {
+                        return Ok(field);
+                    }
+                }
+                Some(c) => field.push(c),
+                None => return Err(self.error_at("quoted field is never closed", start)),
+            }
+        }
+    }
+
+    fn parse_unquoted_field(&mut self) -> Result<String, Error> {
+        let mut field = String::new();
+        while let Some(c) = self.peek() {
+            if c == self.separator || c == '\r' || c == '\n' {
+                break;
+            } else if c == '"' {
+                let offset = self.offset();
+                return Err(self.error_at("unexpected quote in an unquoted field", offset));
+            }
+            field.push(c);
+            self.next();
+        }
+
+        if self.strip_padding {
+            field.truncate(field.trim_end_matches(' ').len());
+        }
+        Ok(field)
+    }
+
+    fn skip_padding(&mut self) {
+        while self.strip_padding && self.separator != ' ' && self.peek() == Some(' ') {
+            self.next();
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, c)| *c)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        self.chars.next().map(|(_, c)| c)
+    }
+
+    fn offset(&mut self) -> usize {
+        match self.chars.peek() {
+            Some((i, _)) => *i,
+            None => self.input.len(),
+        }
+    }
+
+    fn error_at(&self, message: &str, offset: usize) -> Error {
+        Error::cannot_convert_at(
+            CONVERTER_NAME,
+            message,
+            Location::from_offset(self.input, offset),
+        )
+    }
+}
+
+fn looks_tabular(records: &[Vec<String>]) -> bool {
+    records.len() >= 2
+        && records[0].len() >= 2
+        && records.iter().all(|r| r.len() == records[0].len())
+}
+
+fn format_compact(records: &[Vec<String>], separator: char, newline: &str) -> String {
+    records
+        .iter()
+        .map(|record| {
+            record
+                .iter()
+                .map(|field| quote_field(field, separator))
+                .collect::<Vec<String>>()
+                .join(&separator.to_string())
+        })
+        .collect::<Vec<String>>()
+        .join(newline)
+}
+
+fn format_aligned(records: &[Vec<String>], separator: char, spacing: u8, newline: &str) -> String {
+    let quoted: Vec<Vec<String>> = records
+        .iter()
+        .map(|record| record.iter().map(|f| quote_field(f, separator)).collect())
+        .collect();
+
+    let mut widths: Vec<usize> = Vec::new();
+    for record in &quoted {
+        for (i, field) in record.iter().enumerate() {
+            let width = field.chars().count();
+            if i >= widths.len() {
+                widths.push(width);
+            } else if width > widths[i] {
+                widths[i] = width;
+            }
+        }
+    }
+
+    let mut lines = Vec::new();
+    for record in &quoted {
+        let mut line = String::new();
+        for (i, field) in record.iter().enumerate() {
+            line.push_str(field);
+            if i + 1 < record.len() {
+                line.push(separator);
+                let padding = widths[i] - field.chars().count() + usize::from(spacing);
+                line.push_str(&" ".repeat(padding));
+            }
+        }
+        lines.push(line);
+    }
+
+    lines.join(newline)
+}
+
+fn quote_field(field: &str, separator: char) -> String {
+    let needs_quotes = field.contains(separator)
+        || field.contains('"')
+        || field.contains('\n')
+        || field.contains('\r')
+        || field.starts_with(' ')
+        || field.ends_with(' ');
+
+    if needs_quotes {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+struct CsvConverterOptions {
+    separator: Option<char>,
+    spacing: u8,
+    newline: &'static str,
+}
+
+impl Default for CsvConverterOptions {
+    fn default() -> CsvConverterOptions {
+        CsvConverterOptions {
+            separator: None,
+            spacing: 1,
+            newline: "\n",
+        }
+    }
+}
+
+impl CsvConverterOptions {
+    pub fn from_options(converter_options: &HashMap<String, String>) -> CsvConverterOptions {
+        CsvConverterOptions {
+            separator: converter_utils::extract_char(
+                SEPARATOR_OPTION_NAME,
+                converter_options,
+                CsvConverterOptions::default().separator,
+            ),
+            spacing: converter_utils::extract_u8(
+                SPACING_OPTION_NAME,
+                converter_options,
+                CsvConverterOptions::default().spacing,
+            ),
+            newline: converter_utils::extract_newline(converter_options),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::test_options;
+
+    #[test]
+    fn test_convert_invalid() {
+        let converter = CsvConverter::new();
+        let options = test_options("csv", false, &[]);
+
+        assert!(matches!(
+            converter.prettify("a,\"unterminated\nb,c".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("a,b\"c\nd,e".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("\"a\"b,c\nd,e".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("   ".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+    }
+
+    #[test]
+    fn test_convert_not_tabular_unless_requested() {
+        let converter = CsvConverter::new();
+        let options = test_options("all", false, &[]);
+
+        assert!(matches!(
+            converter.prettify("test".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("hello, world".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("a,b\nc,d,e".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert_eq!(
+            converter.prettify("a,b\nc,d,e".as_bytes(), &test_options("CSV", false, &[])),
+            Ok(String::from("a, b\nc, d, e"))
+        );
+    }
+
+    #[test]
+    fn test_convert_aligned() {
+        let converter = CsvConverter::new();
+        let options = test_options("all", false, &[]);
+
+        assert_eq!(
+            converter.prettify(
+                "name,age,city\nalice,30,New York\nbob,4,Paris\n".as_bytes(),
+                &options
+            ),
+            Ok(String::from(
+                "name,  age, city
+alice, 30,  New York
+bob,   4,   Paris"
+            ))
+        );
+        assert_eq!(
+            converter.prettify(
+                "id,quote\r\n1,\"She said \"\"hi\"\"\"\r\n22,\"multi\nline\"\r\n333,\"a, b\""
+                    .as_bytes(),
+                &options
+            ),
+            Ok(String::from(
+                "id,  quote
+1,   \"She said \"\"hi\"\"\"
+22,  \"multi\nline\"
+333, \"a, b\""
+            ))
+        );
+    }
+
+    #[test]
+    fn test_convert_separator_and_spacing() {
+        let converter = CsvConverter::new();
+        let mut options = test_options("all", false, &[]);
+
+        assert_eq!(
+            converter.prettify("a\tbb\tc\nddd\te\tf".as_bytes(), &options),
+            Ok(String::from("a\t   bb\t c\nddd\t e\t  f"))
+        );
+
+        options
+            .converter_specific
+            .insert(String::from(SEPARATOR_OPTION_NAME), String::from(";"));
+        options
+            .converter_specific
+            .insert(String::from(SPACING_OPTION_NAME), String::from("3"));
+        assert_eq!(
+            converter.prettify("a;b,c\nlonger;d".as_bytes(), &options),
+            Ok(String::from("a;        b,c\nlonger;   d"))
+        );
+    }
+
+    #[test]
+    fn test_convert_keeps_spaces_in_fields() {
+        let converter = CsvConverter::new();
+        let options = test_options("csv", false, &[]);
+
+        let formatted = converter
+            .prettify(
+                "x y ,z
+1, 2
+"
+                .as_bytes(),
+                &options,
+            )
+            .unwrap();
+        assert_eq!(formatted, "\"x y \", z\n1,      \" 2\"");
+        assert_eq!(
+            converter.prettify(formatted.as_bytes(), &options),
+            Ok(formatted.clone())
+        );
+        assert_eq!(
+            converter.prettify(formatted.as_bytes(), &test_options("csv", true, &[])),
+            Ok(String::from("\"x y \",z\n1,\" 2\""))
+        );
+    }
+
+    #[test]
+    fn test_convert_reverse() {
+        let converter = CsvConverter::new();
+        let options = test_options("all", true, &[]);
+
+        assert_eq!(
+            converter.prettify(
+                "name,  age, city
+alice, 30,  \"New York, NY\"
+bob,   4,   \" padded \""
+                    .as_bytes(),
+                &options
+            ),
+            Ok(String::from(
+                "name,age,city\nalice,30,\"New York, NY\"\nbob,4,\" padded \""
+            ))
+        );
+    }
+
+    #[test]
+    fn test_convert_error_location() {
+        let converter = CsvConverter::new();
+        let options = test_options("csv", false, &[]);
+
+        assert_eq!(
+            converter.prettify("a,b\nc,\"d".as_bytes(), &options),
+            Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                "quoted field is never closed",
+                Location {
+                    line: 2,
+                    column: 3,
+                    offset: 6
+                }
+            ))
+        );
+        assert_eq!(
+            converter.prettify("a,b\"c".as_bytes(), &options),
+            Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                "unexpected quote in an unquoted field",
+                Location {
+                    line: 1,
+                    column: 4,
+                    offset: 3
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_detect() {
+        let converter = CsvConverter::new();
+
+        assert_eq!(converter.detect("a,b\nc,d".as_bytes()), 50);
+        assert_eq!(converter.detect("a,b\nc,d,e".as_bytes()), 0);
+        assert_eq!(converter.detect("hello, world".as_bytes()), 0);
+    }
+}
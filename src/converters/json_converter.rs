@@ -1,112 +1,314 @@
 use crate::converter_utils;
+use crate::converters::json_stream::JsonStreamFormatter;
 use crate::converters::Converter;
-use crate::error::Error;
-use crate::options::Options;
-use colored_json::{ColoredFormatter, CompactFormatter, PrettyFormatter, Styler};
+use crate::error::{Error, Location};
+use crate::options::{OptionSchema, OptionType, Options};
+use crate::text;
+use colored_json::{ColorMode, ColoredFormatter, CompactFormatter, PrettyFormatter};
 use serde::Serialize;
 use serde_json::Value;
 use std::collections::HashMap;
-use std::iter;
+use std::io::{self, BufRead, Write};
 
+const CONVERTER_NAME: &str = "JSON";
 const INDENT_OPTION_NAME: &str = "indent";
 const COLOR_OPTION_NAME: &str = "color";
+const SORT_KEYS_OPTION_NAME: &str = "sort_keys";
 
+const INDENT_OPTION: OptionSchema = OptionSchema {
+    name: INDENT_OPTION_NAME,
+    option_type: OptionType::Integer { min: 0, max: 16 },
+    default: Some("4"),
+    help: "Number of spaces to indent the output.",
+};
+const COLOR_OPTION: OptionSchema = OptionSchema {
+    name: COLOR_OPTION_NAME,
+    option_type: OptionType::Bool,
+    default: Some("true"),
+    help: "Colors the output.",
+};
+const SORT_KEYS_OPTION: OptionSchema = OptionSchema {
+    name: SORT_KEYS_OPTION_NAME,
+    option_type: OptionType::Bool,
+    default: Some("false"),
+    help: "Sorts object keys alphabetically instead of keeping their original order.",
+};
+
+/// Inputs larger than this many bytes are formatted with the streaming formatter.
+pub const STREAM_THRESHOLD: usize = 64 * 1024 * 1024;
+/// How many bytes of inputs larger than `STREAM_THRESHOLD` are checked to detect them.
+const DETECT_PREFIX_SIZE: usize = 1024 * 1024;
+
+#[derive(Default)]
 pub struct JsonConverter {}
 
 impl JsonConverter {
     pub fn new() -> JsonConverter {
         JsonConverter {}
     }
+
+    pub fn prettify_stream<R: BufRead, W: Write>(
+        &self,
+        reader: R,
+        mut writer: W,
+        options: &Options,
+    ) -> Result<(), Error> {
+        let json_options =
+            JsonConverterOptions::from_options(&options.converter_options(CONVERTER_NAME));
+        if json_options.sort_keys {
+            return Err(Error::cannot_convert(
+                CONVERTER_NAME,
+                "keys can not be sorted while streaming",
+            ));
+        }
+
+        JsonStreamFormatter::new(
+            reader,
+            &mut writer,
+            &json_options.indent,
+            json_options.newline,
+            options.reverse,
+            Some(&options.theme).filter(|_| json_options.color),
+        )
+        .format()?;
+        if json_options.final_newline {
+            writer
+                .write_all(json_options.newline.as_bytes())
+                .map_err(|e| Error::cannot_convert(CONVERTER_NAME, &e.to_string()))?;
+        }
+        Ok(())
+    }
+
+    /// Whether `prettify_stream` ends the output with a line ending.
+    pub fn ends_with_newline(&self, options: &Options) -> bool {
+        JsonConverterOptions::from_options(&options.converter_options(CONVERTER_NAME)).final_newline
+    }
+
+    /// The line ending `prettify_stream` writes, chosen with `end_of_line`.
+    pub fn newline(&self, options: &Options) -> &'static str {
+        JsonConverterOptions::from_options(&options.converter_options(CONVERTER_NAME)).newline
+    }
 }
 
 impl Converter for JsonConverter {
     fn name(&self) -> &str {
-        "JSON"
+        CONVERTER_NAME
     }
 
-    fn options(&self) -> Vec<&str> {
-        vec![INDENT_OPTION_NAME, COLOR_OPTION_NAME]
+    fn options(&self) -> Vec<OptionSchema> {
+        vec![
+            INDENT_OPTION,
+            converter_utils::INDENT_STYLE_OPTION,
+            COLOR_OPTION,
+            SORT_KEYS_OPTION,
+            converter_utils::END_OF_LINE_OPTION,
+            converter_utils::INSERT_FINAL_NEWLINE_OPTION,
+        ]
     }
 
-    fn prettify(&self, input: &str, options: &Options) -> Result<String, Error> {
-        let json_options = JsonConverterOptions::from_options(&options.converter_specific);
+    fn detect(&self, input: &[u8]) -> u8 {
+        let input = match text::decode(input) {
+            Some(input) => input,
+            None => return 0,
+        };
 
-        match serde_json::from_str(input) {
-            Ok(parsed) => format(&parsed, options, &json_options),
-            Err(_) => Err(Error::CannotConvert),
+        if input.len() > STREAM_THRESHOLD {
+            return if starts_like_json(&input, DETECT_PREFIX_SIZE) {
+                100
+            } else {
+                0
+            };
+        }
+
+        match serde_json::from_str::<Value>(&input) {
+            Ok(Value::Object(_)) | Ok(Value::Array(_)) => 100,
+            Ok(_) => 20,
+            Err(_) => 0,
         }
     }
+
+    fn prettify(&self, input: &[u8], options: &Options) -> Result<String, Error> {
+        let input = converter_utils::decode_text(CONVERTER_NAME, input)?;
+        let json_options =
+            JsonConverterOptions::from_options(&options.converter_options(CONVERTER_NAME));
+
+        if input.len() > STREAM_THRESHOLD && !json_options.sort_keys {
+            let mut output = Vec::new();
+            self.prettify_stream(input.as_bytes(), &mut output, options)?;
+            return String::from_utf8(output).map_err(|_| format_error());
+        }
+
+        match serde_json::from_str(&input) {
+            Ok(parsed) => {
+                if json_options.sort_keys {
+                    format(&sort_keys(parsed), options, &json_options)
+                } else {
+                    format(&parsed, options, &json_options)
+                }
+            }
+            Err(e) => Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                converter_utils::strip_location(&e.to_string()),
+                Location::from_line_column(&input, e.line(), e.column()),
+            )),
+        }
+    }
+}
+
+/// Whether input of this size, which starts with the given bytes, is large enough to be formatted
+/// with the streaming formatter without being asked to. Unless the input type is JSON, only input
+/// starting with an object or array is streamed, so other large inputs are still detected.
+pub fn should_stream(input_type: &str, size: u64, start: &[u8]) -> bool {
+    if text::is_utf16(start) {
+        return false;
+    }
+    let start = start.strip_prefix(b"\xef\xbb\xbf").unwrap_or(start);
+    let starts_like_json = start
+        .iter()
+        .find(|b| !b.is_ascii_whitespace())
+        .is_some_and(|b| *b == b'{' || *b == b'[');
+    let is_json = input_type.eq_ignore_ascii_case(CONVERTER_NAME)
+        || (input_type.eq_ignore_ascii_case("all") && starts_like_json);
+    size > STREAM_THRESHOLD as u64 && is_json
+}
+
+/// Whether the input starts with an object or array that is valid JSON up to the given size. Only
+/// a prefix is checked, as the whole input is checked again when it is formatted.
+fn starts_like_json(input: &str, size: usize) -> bool {
+    if !input.trim_start().starts_with(['{', '[']) {
+        return false;
+    }
+    let mut end = size.min(input.len());
+    while !input.is_char_boundary(end) {
+        end -= 1;
+    }
+    let prefix = &input[..end];
+    match JsonStreamFormatter::new(prefix.as_bytes(), io::sink(), "", "\n", true, None).format() {
+        Ok(()) => true,
+        Err(e) => e.location().is_some_and(|l| l.offset >= prefix.len()),
+    }
 }
 
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(String, Value)> = map.into_iter().collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(k, v)| (k, sort_keys(v)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.into_iter().map(sort_keys).collect()),
+        other => other,
+    }
+}
+
+/// The formatters only break lines between values, so their line breaks are changed to the chosen
+/// ones afterwards.
 fn format(
     parsed: &Value,
     options: &Options,
     json_options: &JsonConverterOptions,
+) -> Result<String, Error> {
+    let formatted = format_with_lf(parsed, options, json_options)?;
+    if json_options.newline == "\n" {
+        Ok(formatted)
+    } else {
+        Ok(formatted.replace('\n', json_options.newline))
+    }
+}
+
+fn format_with_lf(
+    parsed: &Value,
+    options: &Options,
+    json_options: &JsonConverterOptions,
 ) -> Result<String, Error> {
     if json_options.color {
         if options.reverse {
-            let formatter = ColoredFormatter::with_styler(CompactFormatter {}, Styler::default());
-            match formatter.to_colored_json_auto(parsed) {
+            let formatter =
+                ColoredFormatter::with_styler(CompactFormatter {}, options.theme.json_styler());
+            match formatter.to_colored_json(parsed, ColorMode::On) {
                 Ok(formatted) => Ok(formatted),
-                Err(_) => Err(Error::CannotConvert),
+                Err(_) => Err(format_error()),
             }
         } else {
-            let indent = get_indent_str(json_options.indent);
             let formatter = ColoredFormatter::with_styler(
-                PrettyFormatter::with_indent(indent.as_bytes()),
-                Styler::default(),
+                PrettyFormatter::with_indent(json_options.indent.as_bytes()),
+                options.theme.json_styler(),
             );
-            match formatter.to_colored_json_auto(parsed) {
+            match formatter.to_colored_json(parsed, ColorMode::On) {
                 Ok(formatted) => Ok(formatted),
-                Err(_) => Err(Error::CannotConvert),
+                Err(_) => Err(format_error()),
             }
         }
     } else {
         if options.reverse {
             Ok(parsed.to_string())
         } else {
-            let indent = get_indent_str(json_options.indent);
             let buf = Vec::new();
-            let formatter = serde_json::ser::PrettyFormatter::with_indent(indent.as_bytes());
+            let formatter =
+                serde_json::ser::PrettyFormatter::with_indent(json_options.indent.as_bytes());
             let mut ser = serde_json::Serializer::with_formatter(buf, formatter);
             parsed.serialize(&mut ser).unwrap();
             match String::from_utf8(ser.into_inner()) {
                 Ok(formatted) => Ok(formatted),
-                Err(_) => Err(Error::CannotConvert),
+                Err(_) => Err(format_error()),
             }
         }
     }
 }
 
-fn get_indent_str(indent: u8) -> String {
-    iter::repeat(" ").take(indent.into()).collect()
+fn format_error() -> Error {
+    Error::cannot_convert(CONVERTER_NAME, "could not format the parsed JSON")
 }
 
 struct JsonConverterOptions {
-    indent: u8,
+    /// The indentation for one level.
+    indent: String,
+    newline: &'static str,
+    final_newline: bool,
     color: bool,
+    sort_keys: bool,
 }
 
-impl JsonConverterOptions {
-    pub fn default() -> JsonConverterOptions {
+impl Default for JsonConverterOptions {
+    fn default() -> JsonConverterOptions {
         JsonConverterOptions {
-            indent: 4,
+            indent: String::from("    "),
+            newline: "\n",
+            final_newline: false,
             color: true,
+            sort_keys: false,
         }
     }
+}
 
+impl JsonConverterOptions {
     pub fn from_options(converter_options: &HashMap<String, String>) -> JsonConverterOptions {
         JsonConverterOptions {
-            indent: converter_utils::extract_u8(
-                INDENT_OPTION_NAME,
+            indent: converter_utils::extract_indent(
                 converter_options,
-                JsonConverterOptions::default().indent,
+                converter_utils::extract_u8(INDENT_OPTION_NAME, converter_options, 4),
+            ),
+            newline: converter_utils::extract_newline(converter_options),
+            final_newline: converter_utils::extract_bool(
+                converter_utils::INSERT_FINAL_NEWLINE_OPTION_NAME,
+                converter_options,
+                JsonConverterOptions::default().final_newline,
             ),
             color: converter_utils::extract_bool(
                 COLOR_OPTION_NAME,
                 converter_options,
                 JsonConverterOptions::default().color,
             ),
+            sort_keys: converter_utils::extract_bool(
+                SORT_KEYS_OPTION_NAME,
+                converter_options,
+                JsonConverterOptions::default().sort_keys,
+            ),
         }
     }
 }
@@ -122,20 +324,18 @@ mod tests {
         json_options.insert(String::from(COLOR_OPTION_NAME), String::from("false"));
         json_options.insert(String::from(INDENT_OPTION_NAME), String::from("4"));
         let options = Options {
-            input_type: String::from("all"),
-            verbose: false,
-            reverse: false,
             converter_specific: json_options,
+            ..Options::default()
         };
 
-        assert_eq!(
-            converter.prettify(&String::from("not json"), &options),
-            Err(Error::CannotConvert)
-        );
-        assert_eq!(
-            converter.prettify(&String::from("{not quite json}"), &options),
-            Err(Error::CannotConvert)
-        );
+        assert!(matches!(
+            converter.prettify("not json".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("{not quite json}".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
     }
 
     #[test]
@@ -145,20 +345,18 @@ mod tests {
         json_options.insert(String::from(COLOR_OPTION_NAME), String::from("true"));
         json_options.insert(String::from(INDENT_OPTION_NAME), String::from("4"));
         let options = Options {
-            input_type: String::from("all"),
-            verbose: false,
-            reverse: false,
             converter_specific: json_options,
+            ..Options::default()
         };
 
-        assert_eq!(
-            converter.prettify(&String::from("not json"), &options),
-            Err(Error::CannotConvert)
-        );
-        assert_eq!(
-            converter.prettify(&String::from("{not quite json}"), &options),
-            Err(Error::CannotConvert)
-        );
+        assert!(matches!(
+            converter.prettify("not json".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("{not quite json}".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
     }
 
     #[test]
@@ -168,14 +366,12 @@ mod tests {
         json_options.insert(String::from(COLOR_OPTION_NAME), String::from("false"));
         json_options.insert(String::from(INDENT_OPTION_NAME), String::from("4"));
         let options = Options {
-            input_type: String::from("all"),
-            verbose: false,
-            reverse: false,
             converter_specific: json_options,
+            ..Options::default()
         };
 
         assert_eq!(
-            converter.prettify(&String::from(r#"{"json": true}"#), &options),
+            converter.prettify(r#"{"json": true}"#.as_bytes(), &options),
             Ok(String::from(
                 r#"{
     "json": true
@@ -184,11 +380,10 @@ mod tests {
         );
         assert_eq!(
             converter.prettify(
-                &String::from(
-                    r#"{
+                r#"{
   "json": false
 }"#
-                ),
+                .as_bytes(),
                 &options
             ),
             Ok(String::from(
@@ -199,9 +394,7 @@ mod tests {
         );
         assert_eq!(
             converter.prettify(
-                &String::from(
-                    r#"{"hello":[{"json":true},{"complex":["field1","field2"]},{"object":{"field3":4}}]}"#,
-                ),
+                r#"{"hello":[{"json":true},{"complex":["field1","field2"]},{"object":{"field3":4}}]}"#.as_bytes(),
                 &options,
             ),
             Ok(String::from(
@@ -234,32 +427,27 @@ mod tests {
         json_options.insert(String::from(COLOR_OPTION_NAME), String::from("true"));
         json_options.insert(String::from(INDENT_OPTION_NAME), String::from("4"));
         let options = Options {
-            input_type: String::from("all"),
-            verbose: false,
-            reverse: false,
             converter_specific: json_options,
+            ..Options::default()
         };
 
         assert_eq!(
-            converter.prettify(&String::from(r#"{"json": true}"#), &options),
+            converter.prettify(r#"{"json": true}"#.as_bytes(), &options),
             Ok(String::from("\u{1b}[1m{\u{1b}[0m\n    \u{1b}[1;34m\"\u{1b}[0m\u{1b}[1;34mjson\u{1b}[0m\u{1b}[1;34m\"\u{1b}[0m: true\u{1b}[1m\n}\u{1b}[0m"))
         );
         assert_eq!(
             converter.prettify(
-                &String::from(
                     r#"{
   "json": false
 }"#
-                ),
+                .as_bytes(),
                 &options
             ),
             Ok(String::from("\u{1b}[1m{\u{1b}[0m\n    \u{1b}[1;34m\"\u{1b}[0m\u{1b}[1;34mjson\u{1b}[0m\u{1b}[1;34m\"\u{1b}[0m: false\u{1b}[1m\n}\u{1b}[0m"))
         );
         assert_eq!(
             converter.prettify(
-                &String::from(
-                    r#"{"hello":[{"json":true},{"complex":["field1","field2"]},{"object":{"field3":4}}]}"#,
-                ),
+                r#"{"hello":[{"json":true},{"complex":["field1","field2"]},{"object":{"field3":4}}]}"#.as_bytes(),
                 &options,
             ),
             Ok(String::from("\u{1b}[1m{\u{1b}[0m\n    \u{1b}[1;34m\"\u{1b}[0m\u{1b}[1;34mhello\u{1b}[0m\u{1b}[1;34m\"\u{1b}[0m: \u{1b}[1m[\u{1b}[0m\n        \u{1b}[1m{\u{1b}[0m\n            \u{1b}[1;34m\"\u{1b}[0m\u{1b}[1;34mjson\u{1b}[0m\u{1b}[1;34m\"\u{1b}[0m: true\u{1b}[1m\n        }\u{1b}[0m,\n        \u{1b}[1m{\u{1b}[0m\n            \u{1b}[1;34m\"\u{1b}[0m\u{1b}[1;34mcomplex\u{1b}[0m\u{1b}[1;34m\"\u{1b}[0m: \u{1b}[1m[\u{1b}[0m\n                \u{1b}[32m\"\u{1b}[0m\u{1b}[32mfield1\u{1b}[0m\u{1b}[32m\"\u{1b}[0m,\n                \u{1b}[32m\"\u{1b}[0m\u{1b}[32mfield2\u{1b}[0m\u{1b}[32m\"\u{1b}[0m\u{1b}[1m\n            ]\u{1b}[0m\u{1b}[1m\n        }\u{1b}[0m,\n        \u{1b}[1m{\u{1b}[0m\n            \u{1b}[1;34m\"\u{1b}[0m\u{1b}[1;34mobject\u{1b}[0m\u{1b}[1;34m\"\u{1b}[0m: \u{1b}[1m{\u{1b}[0m\n                \u{1b}[1;34m\"\u{1b}[0m\u{1b}[1;34mfield3\u{1b}[0m\u{1b}[1;34m\"\u{1b}[0m: 4\u{1b}[1m\n            }\u{1b}[0m\u{1b}[1m\n        }\u{1b}[0m\u{1b}[1m\n    ]\u{1b}[0m\u{1b}[1m\n}\u{1b}[0m"))
@@ -273,14 +461,12 @@ mod tests {
         json_options.insert(String::from(COLOR_OPTION_NAME), String::from("false"));
         json_options.insert(String::from(INDENT_OPTION_NAME), String::from("2"));
         let options = Options {
-            input_type: String::from("all"),
-            verbose: false,
-            reverse: false,
             converter_specific: json_options,
+            ..Options::default()
         };
 
         assert_eq!(
-            converter.prettify(&String::from(r#"{"json":"string"}"#), &options),
+            converter.prettify(r#"{"json":"string"}"#.as_bytes(), &options),
             Ok(String::from(
                 r#"{
   "json": "string"
@@ -296,14 +482,12 @@ mod tests {
         json_options.insert(String::from(COLOR_OPTION_NAME), String::from("true"));
         json_options.insert(String::from(INDENT_OPTION_NAME), String::from("2"));
         let options = Options {
-            input_type: String::from("all"),
-            verbose: false,
-            reverse: false,
             converter_specific: json_options,
+            ..Options::default()
         };
 
         assert_eq!(
-            converter.prettify(&String::from(r#"{"json":"string"}"#), &options),
+            converter.prettify(r#"{"json":"string"}"#.as_bytes(), &options),
             Ok(String::from("\u{1b}[1m{\u{1b}[0m\n  \u{1b}[1;34m\"\u{1b}[0m\u{1b}[1;34mjson\u{1b}[0m\u{1b}[1;34m\"\u{1b}[0m: \u{1b}[32m\"\u{1b}[0m\u{1b}[32mstring\u{1b}[0m\u{1b}[32m\"\u{1b}[0m\u{1b}[1m\n}\u{1b}[0m"))
         );
     }
@@ -315,19 +499,17 @@ mod tests {
         json_options.insert(String::from(COLOR_OPTION_NAME), String::from("false"));
         json_options.insert(String::from(INDENT_OPTION_NAME), String::from("4"));
         let options = Options {
-            input_type: String::from("all"),
-            verbose: false,
             reverse: true,
             converter_specific: json_options,
+            ..Options::default()
         };
 
         assert_eq!(
             converter.prettify(
-                &String::from(
-                    r#"{
+                r#"{
     "json":"string"
 }"#
-                ),
+                .as_bytes(),
                 &options
             ),
             Ok(String::from(r#"{"json":"string"}"#))
@@ -340,19 +522,17 @@ mod tests {
         let mut json_options = HashMap::new();
         json_options.insert(String::from(COLOR_OPTION_NAME), String::from("true"));
         let options = Options {
-            input_type: String::from("all"),
-            verbose: false,
             reverse: true,
             converter_specific: json_options,
+            ..Options::default()
         };
 
         assert_eq!(
             converter.prettify(
-                &String::from(
                     r#"{
     "json":"string"
 }"#
-                ),
+                .as_bytes(),
                 &options
             ),
             Ok(String::from(
@@ -360,4 +540,172 @@ mod tests {
             ))
         );
     }
+
+    #[test]
+    fn test_convert_preserves_key_order() {
+        let converter = JsonConverter::new();
+        let mut json_options = HashMap::new();
+        json_options.insert(String::from(COLOR_OPTION_NAME), String::from("false"));
+        let mut options = Options {
+            reverse: true,
+            converter_specific: json_options,
+            ..Options::default()
+        };
+
+        assert_eq!(
+            converter.prettify(
+                r#"{"zebra": 1, "apple": {"y": true, "b": false}, "mango": [{"2": 2, "1": 1}]}"#
+                    .as_bytes(),
+                &options
+            ),
+            Ok(String::from(
+                r#"{"zebra":1,"apple":{"y":true,"b":false},"mango":[{"2":2,"1":1}]}"#
+            ))
+        );
+
+        options.reverse = false;
+        assert_eq!(
+            converter.prettify(r#"{"b": 1, "a": 2}"#.as_bytes(), &options),
+            Ok(String::from(
+                r#"{
+    "b": 1,
+    "a": 2
+}"#
+            ))
+        );
+    }
+
+    #[test]
+    fn test_convert_sort_keys() {
+        let converter = JsonConverter::new();
+        let mut json_options = HashMap::new();
+        json_options.insert(String::from(COLOR_OPTION_NAME), String::from("false"));
+        json_options.insert(String::from(SORT_KEYS_OPTION_NAME), String::from("true"));
+        let options = Options {
+            reverse: true,
+            converter_specific: json_options,
+            ..Options::default()
+        };
+
+        assert_eq!(
+            converter.prettify(
+                r#"{"zebra": 1, "apple": {"y": true, "b": false}, "mango": [{"2": 2, "1": 1}]}"#
+                    .as_bytes(),
+                &options
+            ),
+            Ok(String::from(
+                r#"{"apple":{"b":false,"y":true},"mango":[{"1":1,"2":2}],"zebra":1}"#
+            ))
+        );
+    }
+
+    #[test]
+    fn test_convert_preserves_numbers() {
+        let converter = JsonConverter::new();
+        let mut json_options = HashMap::new();
+        json_options.insert(String::from(COLOR_OPTION_NAME), String::from("false"));
+        let mut options = Options {
+            converter_specific: json_options,
+            ..Options::default()
+        };
+        let input = String::from(
+            r#"[12345678901234567890123, 0.10000000000000000001, 1.0E+2, 1.50, -0.0, 1e400]"#,
+        );
+
+        assert_eq!(
+            converter.prettify(input.as_bytes(), &options),
+            Ok(String::from(
+                r#"[
+    12345678901234567890123,
+    0.10000000000000000001,
+    1.0E+2,
+    1.50,
+    -0.0,
+    1e400
+]"#
+            ))
+        );
+
+        options.reverse = true;
+        assert_eq!(
+            converter.prettify(input.as_bytes(), &options),
+            Ok(String::from(
+                r#"[12345678901234567890123,0.10000000000000000001,1.0E+2,1.50,-0.0,1e400]"#
+            ))
+        );
+
+        options
+            .converter_specific
+            .insert(String::from(COLOR_OPTION_NAME), String::from("true"));
+        assert_eq!(
+            converter.prettify("[1.50]".as_bytes(), &options),
+            Ok(String::from("\u{1b}[1m[\u{1b}[0m1.50\u{1b}[1m]\u{1b}[0m"))
+        );
+    }
+
+    #[test]
+    fn test_detect() {
+        let converter = JsonConverter::new();
+
+        assert_eq!(converter.detect("{\"a\": 1}".as_bytes()), 100);
+        assert_eq!(converter.detect("[1, 2]".as_bytes()), 100);
+        assert_eq!(converter.detect("\"text\"".as_bytes()), 20);
+        assert_eq!(converter.detect("{\"a\": [1, 2,]}".as_bytes()), 0);
+    }
+
+    #[test]
+    fn test_starts_like_json() {
+        let input = "{\"a\": [1, 2, \"é\"], \"b\": {\"c\": true}}";
+
+        assert!(starts_like_json(input, input.len()));
+        assert!(starts_like_json(input, 8));
+        assert!(starts_like_json(input, 17));
+        assert!(!starts_like_json("{\"a\" 1, \"b\": [", 10));
+        assert!(!starts_like_json("\"text\"", 10));
+    }
+
+    #[test]
+    fn test_should_stream() {
+        let large = STREAM_THRESHOLD as u64 + 1;
+
+        assert!(should_stream("json", large, b"a,b\n1,2"));
+        assert!(should_stream("all", large, b"\n  [1, 2"));
+        assert!(should_stream("all", large, b"{\"a\""));
+        assert!(!should_stream("all", large, b"a,b\n1,2"));
+        assert!(!should_stream("all", large, b"<a>"));
+        assert!(!should_stream("all", large, b""));
+        assert!(!should_stream("csv", large, b"[1, 2]"));
+        assert!(!should_stream("json", STREAM_THRESHOLD as u64, b"[1, 2]"));
+        assert!(should_stream("all", large, b"\xef\xbb\xbf[1]"));
+        assert!(!should_stream("json", large, b"\xff\xfe[\x001\x00]\x00"));
+        assert!(!should_stream("all", large, b"[\x001\x00]\x00"));
+    }
+
+    #[test]
+    fn test_convert_text_encodings() {
+        let converter = JsonConverter::new();
+        let mut json_options = HashMap::new();
+        json_options.insert(String::from(COLOR_OPTION_NAME), String::from("false"));
+        let options = Options {
+            reverse: true,
+            converter_specific: json_options,
+            ..Options::default()
+        };
+
+        assert_eq!(
+            converter.prettify(b"\xef\xbb\xbf{\"a\": 1}", &options),
+            Ok(String::from("{\"a\":1}"))
+        );
+        assert_eq!(
+            converter.prettify(b"\xff\xfe[\x001\x00]\x00", &options),
+            Ok(String::from("[1]"))
+        );
+        assert_eq!(
+            converter.prettify(b"[1, \"\xff\"]", &options),
+            Err(Error::cannot_convert(
+                CONVERTER_NAME,
+                "input is not valid UTF-8 or UTF-16 text"
+            ))
+        );
+    }
 }
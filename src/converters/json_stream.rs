@@ -0,0 +1,470 @@
+use crate::error::{Error, Location};
+use crate::text;
+use crate::theme::{Theme, TokenKind};
+use std::io::{BufRead, ErrorKind, Write};
+
+const CONVERTER_NAME: &str = "JSON";
+
+/// Formats JSON token by token without building the parsed tree, so memory use only depends on
+/// how deeply the input is nested. Strings and numbers are copied as they are written, and
+/// several top level values, like in a JSON Lines file, are each formatted on their own.
+pub struct JsonStreamFormatter<'a, R: BufRead, W: Write> {
+    reader: R,
+    writer: W,
+    indent: Vec<u8>,
+    newline: Vec<u8>,
+    minify: bool,
+    theme: Option<&'a Theme>,
+    containers: Vec<u8>,
+    line: usize,
+    column: usize,
+    offset: usize,
+}
+
+impl<'a, R: BufRead, W: Write> JsonStreamFormatter<'a, R, W> {
+    /// Colors the output with the theme when one is given.
+    pub fn new(
+        reader: R,
+        writer: W,
+        indent: &str,
+        newline: &str,
+        minify: bool,
+        theme: Option<&'a Theme>,
+    ) -> Self {
+        JsonStreamFormatter {
+            reader,
+            writer,
+            indent: indent.as_bytes().to_vec(),
+            newline: newline.as_bytes().to_vec(),
+            minify,
+            theme,
+            containers: Vec::new(),
+            line: 1,
+            column: 1,
+            offset: 0,
+        }
+    }
+
+    pub fn format(mut self) -> Result<(), Error> {
+        self.skip_byte_order_mark()?;
+        let mut values = 0;
+        loop {
+            self.skip_whitespace()?;
+            if self.peek()?.is_none() {
+                break;
+            }
+            if values > 0 {
+                self.writer.write_all(&self.newline).map_err(io_error)?;
+            }
+            self.format_value()?;
+            values += 1;
+        }
+
+        if values == 0 {
+            return Err(self.error("EOF while parsing a value"));
+        }
+        self.writer.flush().map_err(io_error)
+    }
+
+    fn format_value(&mut self) -> Result<(), Error> {
+        loop {
+            self.skip_whitespace()?;
+            match self.peek()? {
+                Some(open @ (b'{' | b'[')) => {
+                    let close = if open == b'{' { b'}' } else { b']' };
+                    self.next()?;
+                    self.skip_whitespace()?;
+                    if self.peek()? == Some(close) {
+                        self.next()?;
+                        self.write_styled(&[open, close], TokenKind::Punctuation)?;
+                    } else {
+                        self.write_styled(&[open], TokenKind::Punctuation)?;
+                        self.containers.push(close);
+                        self.write_newline()?;
+                        if close == b'}' {
+                            self.format_key()?;
+                        }
+                        continue;
+                    }
+                }
+                Some(b'"') => {
+                    let string = self.read_string()?;
+                    self.write_styled(&string, TokenKind::String)?;
+                }
+                Some(b'-' | b'0'..=b'9') => {
+                    let number = self.read_number()?;
+                    self.write_styled(&number, TokenKind::Number)?;
+                }
+                Some(b't') => self.copy_literal(b"true", TokenKind::Boolean)?,
+                Some(b'f') => self.copy_literal(b"false", TokenKind::Boolean)?,
+                Some(b'n') => self.copy_literal(b"null", TokenKind::Null)?,
+                Some(_) => return Err(self.error("expected value")),
+                None => return Err(self.error("EOF while parsing a value")),
+            }
+
+            if self.finish_value()? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Closes every container the last value finished, returning true once the top level value
+    /// is done and false when another value follows a comma.
+    fn finish_value(&mut self) -> Result<bool, Error> {
+        while let Some(close) = self.containers.last().copied() {
+            self.skip_whitespace()?;
+            match self.next()? {
+                Some(b',') => {
+                    self.write(b",")?;
+                    self.write_newline()?;
+                    if close == b'}' {
+                        self.format_key()?;
+                    }
+                    return Ok(false);
+                }
+                Some(c) if c == close => {
+                    self.containers.pop();
+                    self.write_newline()?;
+                    self.write_styled(&[close], TokenKind::Punctuation)?;
+                }
+                Some(_) if close == b'}' => return Err(self.error("expected `,` or `}`")),
+                Some(_) => return Err(self.error("expected `,` or `]`")),
+                None => return Err(self.error("EOF while parsing a value")),
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn format_key(&mut self) -> Result<(), Error> {
+        self.skip_whitespace()?;
+        if self.peek()? != Some(b'"') {
+            return Err(self.error("key must be a string"));
+        }
+        let key = self.read_string()?;
+        self.write_styled(&key, TokenKind::Key)?;
+
+        self.skip_whitespace()?;
+        if self.next()? != Some(b':') {
+            return Err(self.error("expected `:`"));
+        }
+        if self.minify {
+            self.write(b":")
+        } else {
+            self.write(b": ")
+        }
+    }
+
+    fn read_string(&mut self) -> Result<Vec<u8>, Error> {
+        let mut string = Vec::new();
+        string.extend(self.next()?);
+        loop {
+            match self.next()? {
+                Some(b'"') => {
+                    string.push(b'"');
+                    return Ok(string);
+                }
+                Some(b'\\') => {
+                    string.push(b'\\');
+                    match self.next()? {
+                        Some(c @ (b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't')) => {
+                            string.push(c)
+                        }
+                        Some(b'u') => {
+                            string.push(b'u');
+                            for _ in 0..4 {
+                                match self.next()? {
+                                    Some(c) if c.is_ascii_hexdigit() => string.push(c),
+                                    _ => return Err(self.error("invalid escape")),
+                                }
+                            }
+                        }
+                        _ => return Err(self.error("invalid escape")),
+                    }
+                }
+                Some(c) if c < 0x20 => {
+                    return Err(self
+                        .error("control character (\\u0000-\\u001F) found while parsing a string"))
+                }
+                Some(c) => string.push(c),
+                None => return Err(self.error("EOF while parsing a string")),
+            }
+        }
+    }
+
+    fn read_number(&mut self) -> Result<Vec<u8>, Error> {
+        let mut number = Vec::new();
+        if self.peek()? == Some(b'-') {
+            number.extend(self.next()?);
+        }
+
+        match self.peek()? {
+            Some(b'0') => number.extend(self.next()?),
+            Some(b'1'..=b'9') => self.read_digits(&mut number)?,
+            _ => return Err(self.error("invalid number")),
+        }
+        if self.peek()? == Some(b'.') {
+            number.extend(self.next()?);
+            if !matches!(self.peek()?, Some(b'0'..=b'9')) {
+                return Err(self.error("invalid number"));
+            }
+            self.read_digits(&mut number)?;
+        }
+        if matches!(self.peek()?, Some(b'e' | b'E')) {
+            number.extend(self.next()?);
+            if matches!(self.peek()?, Some(b'+' | b'-')) {
+                number.extend(self.next()?);
+            }
+            if !matches!(self.peek()?, Some(b'0'..=b'9')) {
+                return Err(self.error("invalid number"));
+            }
+            self.read_digits(&mut number)?;
+        }
+
+        Ok(number)
+    }
+
+    fn read_digits(&mut self, number: &mut Vec<u8>) -> Result<(), Error> {
+        while let Some(b'0'..=b'9') = self.peek()? {
+            number.extend(self.next()?);
+        }
+        Ok(())
+    }
+
+    fn copy_literal(&mut self, literal: &[u8], kind: TokenKind) -> Result<(), Error> {
+        for expected in literal {
+            if self.next()? != Some(*expected) {
+                return Err(self.error("expected ident"));
+            }
+        }
+        self.write_styled(literal, kind)
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), Error> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek()? {
+            self.next()?;
+        }
+        Ok(())
+    }
+
+    /// Skips a UTF-8 byte order mark. UTF-16 input is refused, as it is only decoded when the
+    /// whole input is read at once.
+    fn skip_byte_order_mark(&mut self) -> Result<(), Error> {
+        self.peek()?;
+        let start = self.reader.fill_buf().map_err(io_error)?;
+        if start.starts_with(b"\xef\xbb\xbf") {
+            self.reader.consume(3);
+            return Ok(());
+        }
+        if text::is_utf16(start) {
+            return Err(Error::cannot_convert(
+                CONVERTER_NAME,
+                "UTF-16 input can not be streamed, it needs to be UTF-8",
+            ));
+        }
+        Ok(())
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, Error> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(buffer) => return Ok(buffer.first().copied()),
+                Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+                Err(e) => return Err(io_error(e)),
+            }
+        }
+    }
+
+    fn next(&mut self) -> Result<Option<u8>, Error> {
+        let next = self.peek()?;
+        if let Some(c) = next {
+            self.reader.consume(1);
+            self.offset += 1;
+            if c == b'\n' {
+                self.line += 1;
+                self.column = 1;
+            } else if c & 0xc0 != 0x80 {
+                self.column += 1;
+            }
+        }
+        Ok(next)
+    }
+
+    fn write_newline(&mut self) -> Result<(), Error> {
+        if self.minify {
+            return Ok(());
+        }
+
+        self.writer.write_all(&self.newline).map_err(io_error)?;
+        for _ in 0..self.containers.len() {
+            self.writer.write_all(&self.indent).map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    fn write_styled(&mut self, bytes: &[u8], kind: TokenKind) -> Result<(), Error> {
+        if let Some(theme) = self.theme {
+            let style = theme.style(kind);
+            write!(self.writer, "{}", style.prefix()).map_err(io_error)?;
+            self.write(bytes)?;
+            write!(self.writer, "{}", style.suffix()).map_err(io_error)
+        } else {
+            self.write(bytes)
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.writer.write_all(bytes).map_err(io_error)
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::cannot_convert_at(
+            CONVERTER_NAME,
+            message,
+            Location {
+                line: self.line,
+                column: self.column,
+                offset: self.offset,
+            },
+        )
+    }
+}
+
+fn io_error(error: std::io::Error) -> Error {
+    Error::cannot_convert(CONVERTER_NAME, &error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn format(input: &str, indent: u8, minify: bool, color: bool) -> Result<String, Error> {
+        let mut output = Vec::new();
+        let theme = Theme::default();
+        let theme = Some(&theme).filter(|_| color);
+        let indent = " ".repeat(indent.into());
+        JsonStreamFormatter::new(input.as_bytes(), &mut output, &indent, "\n", minify, theme)
+            .format()?;
+        Ok(String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_format_pretty() {
+        assert_eq!(
+            format(
+                r#"{"a": [1, -2.5e+3, {}], "b\"": {"c": null, "d": [true, false]}, "e": []}"#,
+                2,
+                false,
+                false
+            ),
+            Ok(String::from(
+                r#"{
+  "a": [
+    1,
+    -2.5e+3,
+    {}
+  ],
+  "b\"": {
+    "c": null,
+    "d": [
+      true,
+      false
+    ]
+  },
+  "e": []
+}"#
+            ))
+        );
+        assert_eq!(
+            format("{\"a\":1}\n{\"a\":2}\n", 2, false, false),
+            Ok(String::from("{\n  \"a\": 1\n}\n{\n  \"a\": 2\n}"))
+        );
+    }
+
+    #[test]
+    fn test_format_minify_and_color() {
+        assert_eq!(
+            format("{\n  \"a\": [ 1, \"x\" ]\n}", 4, true, false),
+            Ok(String::from("{\"a\":[1,\"x\"]}"))
+        );
+        assert_eq!(
+            format("{\"a\": \"x\"}", 4, true, true),
+            Ok(String::from(
+                "\u{1b}[1m{\u{1b}[0m\u{1b}[1;34m\"a\"\u{1b}[0m:\u{1b}[32m\"x\"\u{1b}[0m\u{1b}[1m}\u{1b}[0m"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_format_tabs_and_crlf() {
+        let mut output = Vec::new();
+        JsonStreamFormatter::new(
+            "{\"a\":[1]}".as_bytes(),
+            &mut output,
+            "\t",
+            "\r\n",
+            false,
+            None,
+        )
+        .format()
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\r\n\t\"a\": [\r\n\t\t1\r\n\t]\r\n}"
+        );
+    }
+
+    #[test]
+    fn test_format_encodings() {
+        assert_eq!(
+            format("\u{feff}[1]", 2, false, false),
+            Ok(String::from("[\n  1\n]"))
+        );
+
+        let utf16: Vec<u8> = "[1]".encode_utf16().flat_map(u16::to_le_bytes).collect();
+        let mut output = Vec::new();
+        assert_eq!(
+            JsonStreamFormatter::new(&utf16[..], &mut output, "  ", "\n", false, None).format(),
+            Err(Error::cannot_convert(
+                CONVERTER_NAME,
+                "UTF-16 input can not be streamed, it needs to be UTF-8"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_format_invalid() {
+        assert_eq!(
+            format("{\"a\": [1,\n 2,]}", 2, false, false),
+            Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                "expected value",
+                Location {
+                    line: 2,
+                    column: 4,
+                    offset: 13
+                }
+            ))
+        );
+        assert!(matches!(
+            format("{\"a\" 1}", 2, false, false),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            format("[01]", 2, false, false),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            format("[\"a\\x\"]", 2, false, false),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            format("[tru]", 2, false, false),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            format("  ", 2, false, false),
+            Err(Error::CannotConvert { .. })
+        ));
+    }
+}
@@ -0,0 +1,649 @@
+use crate::converter_utils;
+use crate::converters::Converter;
+use crate::error::{Error, Location};
+use crate::options::{OptionSchema, OptionType, Options};
+use crate::text;
+use crate::theme::{Theme, TokenKind};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::collections::HashMap;
+
+const CONVERTER_NAME: &str = "XML";
+const INDENT_OPTION_NAME: &str = "indent";
+const COLOR_OPTION_NAME: &str = "color";
+
+const INDENT_OPTION: OptionSchema = OptionSchema {
+    name: INDENT_OPTION_NAME,
+    option_type: OptionType::Integer { min: 0, max: 16 },
+    default: Some("4"),
+    help: "Number of spaces to indent the output.",
+};
+const COLOR_OPTION: OptionSchema = OptionSchema {
+    name: COLOR_OPTION_NAME,
+    option_type: OptionType::Bool,
+    default: Some("true"),
+    help: "Colors the output.",
+};
+
+#[derive(Default)]
+pub struct XmlConverter {}
+
+impl XmlConverter {
+    pub fn new() -> XmlConverter {
+        XmlConverter {}
+    }
+}
+
+impl Converter for XmlConverter {
+    fn name(&self) -> &str {
+        CONVERTER_NAME
+    }
+
+    fn options(&self) -> Vec<OptionSchema> {
+        vec![
+            INDENT_OPTION,
+            converter_utils::INDENT_STYLE_OPTION,
+            COLOR_OPTION,
+            converter_utils::END_OF_LINE_OPTION,
+            converter_utils::INSERT_FINAL_NEWLINE_OPTION,
+        ]
+    }
+
+    fn detect(&self, input: &[u8]) -> u8 {
+        let input = match text::decode(input) {
+            Some(input) => input,
+            None => return 0,
+        };
+
+        match parse(&input) {
+            Ok(_) => 90,
+            Err(_) => 0,
+        }
+    }
+
+    fn prettify(&self, input: &[u8], options: &Options) -> Result<String, Error> {
+        let xml_options =
+            XmlConverterOptions::from_options(&options.converter_options(CONVERTER_NAME));
+        let input = converter_utils::decode_text(CONVERTER_NAME, input)?;
+        let document = parse(&input)?;
+
+        let mut writer = XmlWriter::new(options.reverse, &xml_options, &options.theme);
+        writer.write_document(&document);
+        Ok(writer.output)
+    }
+}
+
+enum Node {
+    Element(Element),
+    Text(String),
+    CData(String),
+    Comment(String),
+    ProcessingInstruction(String),
+    DocType(String),
+}
+
+struct Element {
+    name: String,
+    attributes: Vec<(String, String)>,
+    children: Vec<Node>,
+    self_closing: bool,
+    offset: usize,
+}
+
+impl Element {
+    fn from_start(
+        start: &BytesStart,
+        self_closing: bool,
+        input: &str,
+        offset: usize,
+    ) -> Result<Element, Error> {
+        let mut attributes = Vec::new();
+        for attribute in start.attributes() {
+            match attribute {
+                Ok(a) => attributes.push((bytes_to_string(a.key)?, bytes_to_string(&a.value)?)),
+                Err(e) => return Err(error_at(input, &e.to_string(), offset)),
+            }
+        }
+
+        Ok(Element {
+            name: bytes_to_string(start.name())?,
+            attributes,
+            children: Vec::new(),
+            self_closing,
+            offset,
+        })
+    }
+}
+
+fn parse(input: &str) -> Result<Vec<Node>, Error> {
+    let mut reader = Reader::from_str(input);
+    reader.trim_text(false);
+    reader.check_comments(true);
+
+    let mut document = Vec::new();
+    let mut open_elements: Vec<Element> = Vec::new();
+    let mut has_root = false;
+    let mut buf = Vec::new();
+    loop {
+        let offset = reader.buffer_position();
+        let node = match reader.read_event(&mut buf) {
+            Ok(Event::Start(e)) => {
+                open_elements.push(Element::from_start(&e, false, input, offset)?);
+                buf.clear();
+                continue;
+            }
+            Ok(Event::End(e)) => match open_elements.pop() {
+                Some(element) => Node::Element(element),
+                None => {
+                    let message = format!("unexpected closing tag </{}>", bytes_to_string(&e)?);
+                    return Err(error_at(input, &message, offset));
+                }
+            },
+            Ok(Event::Empty(e)) => Node::Element(Element::from_start(&e, true, input, offset)?),
+            Ok(Event::Text(e)) => {
+                let text = bytes_to_string(&e)?;
+                if text.trim().is_empty() && open_elements.is_empty() {
+                    buf.clear();
+                    continue;
+                }
+                Node::Text(text)
+            }
+            Ok(Event::CData(e)) => Node::CData(bytes_to_string(&e)?),
+            Ok(Event::Comment(e)) => Node::Comment(bytes_to_string(&e)?),
+            Ok(Event::Decl(e)) => Node::ProcessingInstruction(bytes_to_string(&e)?),
+            Ok(Event::PI(e)) => Node::ProcessingInstruction(bytes_to_string(&e)?),
+            Ok(Event::DocType(e)) => Node::DocType(bytes_to_string(&e)?),
+            Ok(Event::Eof) => break,
+            Err(e) => {
+                return Err(error_at(input, &e.to_string(), offset));
+            }
+        };
+
+        match open_elements.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => {
+                match node {
+                    Node::Element(_) if has_root => {
+                        return Err(error_at(input, "found more than one root element", offset));
+                    }
+                    Node::Element(_) => has_root = true,
+                    Node::Text(_) | Node::CData(_) => {
+                        return Err(error_at(
+                            input,
+                            "found text outside the root element",
+                            offset,
+                        ));
+                    }
+                    _ => (),
+                }
+                document.push(node);
+            }
+        }
+        buf.clear();
+    }
+
+    if let Some(element) = open_elements.last() {
+        let message = format!("element <{}> is never closed", element.name);
+        return Err(error_at(input, &message, element.offset));
+    } else if !has_root {
+        return Err(error_at(input, "no root element found", input.len()));
+    }
+
+    Ok(document)
+}
+
+fn bytes_to_string(bytes: &[u8]) -> Result<String, Error> {
+    match String::from_utf8(bytes.to_vec()) {
+        Ok(string) => Ok(string),
+        Err(_) => Err(Error::cannot_convert(
+            CONVERTER_NAME,
+            "input is not valid UTF-8",
+        )),
+    }
+}
+
+fn error_at(input: &str, message: &str, offset: usize) -> Error {
+    Error::cannot_convert_at(
+        CONVERTER_NAME,
+        message,
+        Location::from_offset(input, offset),
+    )
+}
+
+struct XmlWriter {
+    output: String,
+    reverse: bool,
+    indent: String,
+    newline: &'static str,
+    theme: Option<Theme>,
+}
+
+impl XmlWriter {
+    fn new(reverse: bool, xml_options: &XmlConverterOptions, theme: &Theme) -> XmlWriter {
+        XmlWriter {
+            output: String::new(),
+            reverse,
+            indent: xml_options.indent.clone(),
+            newline: xml_options.newline,
+            theme: if xml_options.color {
+                Some(theme.clone())
+            } else {
+                None
+            },
+        }
+    }
+
+    fn write_document(&mut self, document: &[Node]) {
+        for (i, node) in document.iter().enumerate() {
+            if i > 0 {
+                self.write_newline();
+            }
+            self.write_node(node, 0);
+        }
+    }
+
+    fn write_node(&mut self, node: &Node, depth: usize) {
+        match node {
+            Node::Element(element) => self.write_element(element, depth),
+            Node::Text(text) => self.output.push_str(text),
+            Node::CData(data) => self.write_markup("<![CDATA[", data, "]]>", TokenKind::CData),
+            Node::Comment(comment) => self.write_markup("<!--", comment, "-->", TokenKind::Comment),
+            Node::ProcessingInstruction(instruction) => {
+                self.write_markup("<?", instruction, "?>", TokenKind::Declaration)
+            }
+            Node::DocType(doctype) => {
+                self.write_markup("<!DOCTYPE ", doctype, ">", TokenKind::Declaration)
+            }
+        }
+    }
+
+    fn write_element(&mut self, element: &Element, depth: usize) {
+        self.write_start_tag(element);
+        if element.self_closing {
+            return;
+        }
+
+        if has_text(element) {
+            for child in &element.children {
+                self.write_verbatim(child);
+            }
+        } else {
+            // Whitespace between child elements is only layout, which is replaced.
+            let children = element.children.iter().filter(|c| !is_whitespace(c));
+            if self.reverse || element.children.iter().all(is_whitespace) {
+                for child in children {
+                    self.write_node(child, depth + 1);
+                }
+            } else {
+                for child in children {
+                    self.write_newline();
+                    self.write_indent(depth + 1);
+                    self.write_node(child, depth + 1);
+                }
+                self.write_newline();
+                self.write_indent(depth);
+            }
+        }
+
+        let end_tag = format!("</{}>", element.name);
+        self.push_styled(&end_tag, TokenKind::Tag);
+    }
+
+    /// Writes mixed content as it is, since any whitespace added or removed there would change the
+    /// text.
+    fn write_verbatim(&mut self, node: &Node) {
+        match node {
+            Node::Element(element) => {
+                self.write_start_tag(element);
+                if element.self_closing {
+                    return;
+                }
+                for child in &element.children {
+                    self.write_verbatim(child);
+                }
+                let end_tag = format!("</{}>", element.name);
+                self.push_styled(&end_tag, TokenKind::Tag);
+            }
+            _ => self.write_node(node, 0),
+        }
+    }
+
+    fn write_start_tag(&mut self, element: &Element) {
+        self.push_styled(&format!("<{}", element.name), TokenKind::Tag);
+        for (key, value) in &element.attributes {
+            self.output.push(' ');
+            self.push_styled(key, TokenKind::Attribute);
+            self.output.push('=');
+            self.push_styled(&quote_attribute_value(value), TokenKind::String);
+        }
+
+        if element.self_closing {
+            self.push_styled("/>", TokenKind::Tag);
+        } else {
+            self.push_styled(">", TokenKind::Tag);
+        }
+    }
+
+    fn write_markup(&mut self, start: &str, content: &str, end: &str, kind: TokenKind) {
+        self.push_styled(&format!("{}{}{}", start, content, end), kind);
+    }
+
+    fn write_newline(&mut self) {
+        if !self.reverse {
+            self.output.push_str(self.newline);
+        }
+    }
+
+    fn write_indent(&mut self, depth: usize) {
+        for _ in 0..depth {
+            self.output.push_str(&self.indent);
+        }
+    }
+
+    fn push_styled(&mut self, text: &str, kind: TokenKind) {
+        match &self.theme {
+            Some(theme) => self.output.push_str(&theme.paint(kind, text)),
+            None => self.output.push_str(text),
+        }
+    }
+}
+
+/// Whether the element has mixed content, with text that is more than whitespace.
+fn has_text(element: &Element) -> bool {
+    element
+        .children
+        .iter()
+        .any(|child| matches!(child, Node::Text(text) if !text.trim().is_empty()))
+}
+
+fn is_whitespace(node: &Node) -> bool {
+    matches!(node, Node::Text(text) if text.trim().is_empty())
+}
+
+fn quote_attribute_value(value: &str) -> String {
+    if value.contains('"') {
+        format!("'{}'", value)
+    } else {
+        format!("\"{}\"", value)
+    }
+}
+
+struct XmlConverterOptions {
+    /// The indentation for one level.
+    indent: String,
+    newline: &'static str,
+    color: bool,
+}
+
+impl Default for XmlConverterOptions {
+    fn default() -> XmlConverterOptions {
+        XmlConverterOptions {
+            indent: String::from("    "),
+            newline: "\n",
+            color: true,
+        }
+    }
+}
+
+impl XmlConverterOptions {
+    pub fn from_options(converter_options: &HashMap<String, String>) -> XmlConverterOptions {
+        XmlConverterOptions {
+            indent: converter_utils::extract_indent(
+                converter_options,
+                converter_utils::extract_u8(INDENT_OPTION_NAME, converter_options, 4),
+            ),
+            newline: converter_utils::extract_newline(converter_options),
+            color: converter_utils::extract_bool(
+                COLOR_OPTION_NAME,
+                converter_options,
+                XmlConverterOptions::default().color,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::test_options;
+
+    #[test]
+    fn test_convert_invalid() {
+        let converter = XmlConverter::new();
+        let options = test_options(
+            "all",
+            false,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "4")],
+        );
+
+        assert!(matches!(
+            converter.prettify("not xml".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("<a><b></a>".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("<a>".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("<a/><b/>".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify(r#"{"json": true}"#.as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+    }
+
+    #[test]
+    fn test_convert_no_color() {
+        let converter = XmlConverter::new();
+        let options = test_options(
+            "all",
+            false,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "4")],
+        );
+
+        assert_eq!(
+            converter.prettify("<a><b>text</b><c/></a>".as_bytes(), &options),
+            Ok(String::from(
+                r#"<a>
+    <b>text</b>
+    <c/>
+</a>"#
+            ))
+        );
+        assert_eq!(
+            converter.prettify(
+                    r#"<?xml version="1.0"?><!DOCTYPE note SYSTEM "note.dtd"><!-- a note --><n:note xmlns:n="urn:note" id='a"b'><n:to lang="en">Tove</n:to><?render fast?><n:body><![CDATA[<not a tag>]]></n:body><empty></empty></n:note>"#
+                .as_bytes(),
+                &options
+            ),
+            Ok(String::from(
+                r#"<?xml version="1.0"?>
+<!DOCTYPE note SYSTEM "note.dtd">
+<!-- a note -->
+<n:note xmlns:n="urn:note" id='a"b'>
+    <n:to lang="en">Tove</n:to>
+    <?render fast?>
+    <n:body>
+        <![CDATA[<not a tag>]]>
+    </n:body>
+    <empty></empty>
+</n:note>"#
+            ))
+        );
+    }
+
+    #[test]
+    fn test_convert_different_indent_size() {
+        let converter = XmlConverter::new();
+        let options = test_options(
+            "all",
+            false,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")],
+        );
+
+        assert_eq!(
+            converter.prettify("<a><b><c>&amp;</c></b></a>".as_bytes(), &options),
+            Ok(String::from(
+                r#"<a>
+  <b>
+    <c>&amp;</c>
+  </b>
+</a>"#
+            ))
+        );
+    }
+
+    #[test]
+    fn test_convert_mixed_content() {
+        let converter = XmlConverter::new();
+        let input = "<doc><p>Hello <b>world</b>, <i> again </i></p>\n<p>  spaced  </p></doc>";
+
+        assert_eq!(
+            converter.prettify(
+                input.as_bytes(),
+                &test_options(
+                    "all",
+                    false,
+                    &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")]
+                )
+            ),
+            Ok(String::from(
+                "<doc>\n  <p>Hello <b>world</b>, <i> again </i></p>\n  <p>  spaced  </p>\n</doc>"
+            ))
+        );
+        assert_eq!(
+            converter.prettify(
+                input.as_bytes(),
+                &test_options(
+                    "all",
+                    true,
+                    &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")]
+                )
+            ),
+            Ok(String::from(
+                "<doc><p>Hello <b>world</b>, <i> again </i></p><p>  spaced  </p></doc>"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_convert_tab_indent() {
+        let converter = XmlConverter::new();
+        let mut options = test_options(
+            "all",
+            false,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")],
+        );
+        options
+            .converter_specific
+            .insert(String::from("indent_style"), String::from("tab"));
+
+        assert_eq!(
+            converter.prettify("<a><b><c/></b></a>".as_bytes(), &options),
+            Ok(String::from("<a>\n\t<b>\n\t\t<c/>\n\t</b>\n</a>"))
+        );
+    }
+
+    #[test]
+    fn test_convert_color() {
+        let converter = XmlConverter::new();
+        let options = test_options(
+            "all",
+            false,
+            &[(COLOR_OPTION_NAME, "true"), (INDENT_OPTION_NAME, "4")],
+        );
+
+        assert_eq!(
+            converter.prettify(r#"<a x="1"><b/></a>"#.as_bytes(), &options),
+            Ok(String::from("\u{1b}[1;34m<a\u{1b}[0m \u{1b}[36mx\u{1b}[0m=\u{1b}[32m\"1\"\u{1b}[0m\u{1b}[1;34m>\u{1b}[0m\n    \u{1b}[1;34m<b\u{1b}[0m\u{1b}[1;34m/>\u{1b}[0m\n\u{1b}[1;34m</a>\u{1b}[0m"))
+        );
+    }
+
+    #[test]
+    fn test_convert_reverse() {
+        let converter = XmlConverter::new();
+        let options = test_options(
+            "all",
+            true,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "4")],
+        );
+
+        assert_eq!(
+            converter.prettify(
+                r#"<?xml version="1.0"?>
+<a>
+    <b>text</b>
+    <!-- comment -->
+    <c attr="value"/>
+</a>"#
+                    .as_bytes(),
+                &options
+            ),
+            Ok(String::from(
+                r#"<?xml version="1.0"?><a><b>text</b><!-- comment --><c attr="value"/></a>"#
+            ))
+        );
+    }
+
+    #[test]
+    fn test_convert_error_location() {
+        let converter = XmlConverter::new();
+        let options = test_options(
+            "all",
+            false,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "4")],
+        );
+
+        assert_eq!(
+            converter.prettify("<a>\n  <b>\n</a>".as_bytes(), &options),
+            Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                "Expecting </b> found </a>",
+                Location {
+                    line: 3,
+                    column: 1,
+                    offset: 10
+                }
+            ))
+        );
+        assert_eq!(
+            converter.prettify("<a>\n  <b>".as_bytes(), &options),
+            Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                "element <b> is never closed",
+                Location {
+                    line: 2,
+                    column: 3,
+                    offset: 6
+                }
+            ))
+        );
+        assert_eq!(
+            converter.prettify("<a/>\n<b/>".as_bytes(), &options),
+            Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                "found more than one root element",
+                Location {
+                    line: 2,
+                    column: 1,
+                    offset: 5
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_detect() {
+        let converter = XmlConverter::new();
+
+        assert_eq!(converter.detect("<a><b>text</b></a>".as_bytes()), 90);
+        assert_eq!(converter.detect("<a><b>text</a>".as_bytes()), 0);
+        assert_eq!(converter.detect("text".as_bytes()), 0);
+    }
+}
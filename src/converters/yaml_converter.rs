@@ -0,0 +1,1116 @@
+use crate::converter_utils;
+use crate::converters::Converter;
+use crate::error::{Error, Location};
+use crate::options::{OptionSchema, OptionType, Options};
+use crate::text;
+use crate::theme::{Theme, TokenKind};
+use std::collections::HashMap;
+use yaml_rust::parser::{EventReceiver, Parser};
+use yaml_rust::scanner::{Marker, Scanner, TScalarStyle, Token, TokenType};
+use yaml_rust::Event;
+
+const CONVERTER_NAME: &str = "YAML";
+const INDENT_OPTION_NAME: &str = "indent";
+const COLOR_OPTION_NAME: &str = "color";
+const DROP_COMMENTS_OPTION_NAME: &str = "drop_comments";
+
+const INDENT_OPTION: OptionSchema = OptionSchema {
+    name: INDENT_OPTION_NAME,
+    option_type: OptionType::Integer { min: 0, max: 16 },
+    default: Some("2"),
+    help: "Number of spaces to indent the output.",
+};
+const COLOR_OPTION: OptionSchema = OptionSchema {
+    name: COLOR_OPTION_NAME,
+    option_type: OptionType::Bool,
+    default: Some("true"),
+    help: "Colors the output.",
+};
+const DROP_COMMENTS_OPTION: OptionSchema = OptionSchema {
+    name: DROP_COMMENTS_OPTION_NAME,
+    option_type: OptionType::Bool,
+    default: Some("false"),
+    help: "Formats input with comments, which are not kept in the output.",
+};
+
+#[derive(Default)]
+pub struct YamlConverter {}
+
+impl YamlConverter {
+    pub fn new() -> YamlConverter {
+        YamlConverter {}
+    }
+}
+
+impl Converter for YamlConverter {
+    fn name(&self) -> &str {
+        CONVERTER_NAME
+    }
+
+    fn options(&self) -> Vec<OptionSchema> {
+        vec![
+            INDENT_OPTION,
+            COLOR_OPTION,
+            DROP_COMMENTS_OPTION,
+            converter_utils::END_OF_LINE_OPTION,
+            converter_utils::INSERT_FINAL_NEWLINE_OPTION,
+        ]
+    }
+
+    fn detect(&self, input: &[u8]) -> u8 {
+        let input = match text::decode(input) {
+            Some(input) => input,
+            None => return 0,
+        };
+
+        match parse(&input) {
+            Ok(documents) if documents.iter().any(Node::is_collection) => {
+                if input.trim_start().starts_with(['{', '[']) {
+                    40
+                } else {
+                    70
+                }
+            }
+            _ => 0,
+        }
+    }
+
+    fn prettify(&self, input: &[u8], options: &Options) -> Result<String, Error> {
+        let yaml_options =
+            YamlConverterOptions::from_options(&options.converter_options(CONVERTER_NAME));
+        let input = converter_utils::decode_text(CONVERTER_NAME, input)?;
+        let documents = parse(&input)?;
+
+        if !options.input_type.eq_ignore_ascii_case(self.name())
+            && !documents.iter().any(Node::is_collection)
+        {
+            return Err(Error::cannot_convert(
+                CONVERTER_NAME,
+                "needs to contain a mapping or sequence to guess it is YAML",
+            ));
+        }
+
+        let properties = scan_properties(&input);
+        if let Some(mark) = properties.tag_directive {
+            return Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                "%TAG directives would be lost",
+                Location::from_line_column(&input, mark.line(), mark.col() + 1),
+            ));
+        }
+        if properties.tags > documents.iter().map(Node::scalar_tags).sum() {
+            return Err(Error::cannot_convert(
+                CONVERTER_NAME,
+                "tags on mappings and sequences would be lost",
+            ));
+        }
+
+        if !yaml_options.drop_comments {
+            if let Some(offset) = find_comment(&input, &documents) {
+                return Err(Error::cannot_convert_at(
+                    CONVERTER_NAME,
+                    "comments would be lost, set yaml.drop_comments=true to format it anyway",
+                    Location::from_offset(&input, offset),
+                ));
+            }
+        }
+
+        let mut writer = YamlWriter::new(&yaml_options, &options.theme, properties.anchor_names);
+        let formatted: Vec<String> = documents
+            .iter()
+            .map(|document| {
+                if options.reverse {
+                    writer.write_flow_document(document)
+                } else {
+                    writer.write_block_document(document)
+                }
+            })
+            .collect();
+
+        let mut output = String::new();
+        for (i, document) in formatted.iter().enumerate() {
+            if i > 0 {
+                if !output.ends_with(yaml_options.newline) {
+                    output.push_str(yaml_options.newline);
+                }
+                output.push_str("---");
+                output.push_str(yaml_options.newline);
+            }
+            output.push_str(document);
+        }
+        Ok(output)
+    }
+}
+
+#[derive(PartialEq)]
+enum Node {
+    Scalar(Scalar),
+    Sequence(Collection<Node>),
+    Mapping(Collection<(Node, Node)>),
+    Alias(usize),
+}
+
+impl Node {
+    fn is_collection(&self) -> bool {
+        matches!(self, Node::Sequence(_) | Node::Mapping(_))
+    }
+
+    /// How many scalars in the node have a tag.
+    fn scalar_tags(&self) -> usize {
+        match self {
+            Node::Scalar(scalar) => usize::from(scalar.tag.is_some()),
+            Node::Sequence(sequence) => sequence.items.iter().map(Node::scalar_tags).sum(),
+            Node::Mapping(mapping) => mapping
+                .items
+                .iter()
+                .map(|(key, value)| key.scalar_tags() + value.scalar_tags())
+                .sum(),
+            Node::Alias(_) => 0,
+        }
+    }
+
+    fn is_empty_collection(&self) -> bool {
+        match self {
+            Node::Sequence(sequence) => sequence.items.is_empty(),
+            Node::Mapping(mapping) => mapping.items.is_empty(),
+            _ => false,
+        }
+    }
+}
+
+#[derive(PartialEq)]
+struct Scalar {
+    value: String,
+    style: TScalarStyle,
+    anchor: usize,
+    tag: Option<TokenType>,
+}
+
+impl Scalar {
+    fn is_string(&self) -> bool {
+        self.style != TScalarStyle::Plain || !is_plain_non_string(&self.value)
+    }
+}
+
+#[derive(PartialEq)]
+struct Collection<T> {
+    items: Vec<T>,
+    anchor: usize,
+}
+
+enum OpenNode {
+    Sequence(Collection<Node>),
+    Mapping(Collection<(Node, Node)>, Option<Node>),
+}
+
+#[derive(Default)]
+struct TreeBuilder {
+    documents: Vec<Node>,
+    open_nodes: Vec<OpenNode>,
+}
+
+impl TreeBuilder {
+    fn push_node(&mut self, node: Node) {
+        match self.open_nodes.last_mut() {
+            Some(OpenNode::Mapping(mapping, pending_key)) => match pending_key.take() {
+                Some(key) => mapping.items.push((key, node)),
+                None => *pending_key = Some(node),
+            },
+            Some(OpenNode::Sequence(sequence)) => sequence.items.push(node),
+            None => self.documents.push(node),
+        }
+    }
+}
+
+impl EventReceiver for TreeBuilder {
+    fn on_event(&mut self, event: Event) {
+        match event {
+            Event::Scalar(value, style, anchor, tag) => self.push_node(Node::Scalar(Scalar {
+                value,
+                style,
+                anchor,
+                tag,
+            })),
+            Event::Alias(anchor) => self.push_node(Node::Alias(anchor)),
+            Event::SequenceStart(anchor) => self.open_nodes.push(OpenNode::Sequence(Collection {
+                items: Vec::new(),
+                anchor,
+            })),
+            Event::MappingStart(anchor) => self.open_nodes.push(OpenNode::Mapping(
+                Collection {
+                    items: Vec::new(),
+                    anchor,
+                },
+                None,
+            )),
+            Event::SequenceEnd | Event::MappingEnd => match self.open_nodes.pop() {
+                Some(OpenNode::Sequence(sequence)) => self.push_node(Node::Sequence(sequence)),
+                Some(OpenNode::Mapping(mapping, _)) => self.push_node(Node::Mapping(mapping)),
+                None => (),
+            },
+            _ => (),
+        }
+    }
+}
+
+fn parse(input: &str) -> Result<Vec<Node>, Error> {
+    let mut builder = TreeBuilder::default();
+    let mut parser = Parser::new(input.chars());
+    match parser.load(&mut builder, true) {
+        Ok(_) if builder.documents.is_empty() => Err(Error::cannot_convert(
+            CONVERTER_NAME,
+            "input has no documents",
+        )),
+        Ok(_) => Ok(builder.documents),
+        Err(e) => Err(Error::cannot_convert_at(
+            CONVERTER_NAME,
+            converter_utils::strip_location(&e.to_string()),
+            Location::from_line_column(input, e.marker().line(), e.marker().col() + 1),
+        )),
+    }
+}
+
+/// What the parser leaves out of its events, found by scanning the input again.
+#[derive(Default)]
+struct Properties {
+    /// The names of the anchors. The parser numbers anchors from 1 in the order they are written,
+    /// so the name of anchor `n` is at index `n - 1`.
+    anchor_names: Vec<String>,
+    /// How many tags there are. The parser only keeps the tags of scalars.
+    tags: usize,
+    tag_directive: Option<Marker>,
+}
+
+fn scan_properties(input: &str) -> Properties {
+    let mut properties = Properties::default();
+    for Token(mark, token) in Scanner::new(input.chars()) {
+        match token {
+            TokenType::Anchor(name) => properties.anchor_names.push(name),
+            TokenType::Tag(..) => properties.tags += 1,
+            TokenType::TagDirective(..) => {
+                properties.tag_directive.get_or_insert(mark);
+            }
+            _ => (),
+        }
+    }
+    properties
+}
+
+/// Finds the offset of the first comment in the input. The parser skips comments without saying
+/// where they were, so a `#` starting a line or following whitespace is taken as a comment when
+/// removing it and the rest of its line leaves the documents the same.
+fn find_comment(input: &str, documents: &[Node]) -> Option<usize> {
+    let candidates: Vec<usize> = input
+        .match_indices('#')
+        .map(|(i, _)| i)
+        .filter(|&i| i == 0 || input[..i].ends_with([' ', '\t', '\n', '\r']))
+        .collect();
+    let unchanged = |candidates: &[usize]| {
+        parse(&remove_line_ends(input, candidates)).is_ok_and(|parsed| parsed == documents)
+    };
+
+    match candidates.first() {
+        None => None,
+        Some(first) if unchanged(&candidates) => Some(*first),
+        _ => candidates.into_iter().find(|&i| unchanged(&[i])),
+    }
+}
+
+/// Removes the text from each of the offsets to the end of its line.
+fn remove_line_ends(input: &str, offsets: &[usize]) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut kept_from = 0;
+    for &offset in offsets {
+        if offset < kept_from {
+            continue;
+        }
+        output.push_str(&input[kept_from..offset]);
+        kept_from = input[offset..]
+            .find(['\r', '\n'])
+            .map_or(input.len(), |end| offset + end);
+    }
+    output.push_str(&input[kept_from..]);
+    output
+}
+
+fn is_plain_non_string(value: &str) -> bool {
+    match value {
+        "" | "~" | "null" | "Null" | "NULL" => true,
+        "true" | "True" | "TRUE" | "false" | "False" | "FALSE" => true,
+        ".nan" | ".NaN" | ".NAN" => true,
+        _ => is_yaml_number(value),
+    }
+}
+
+fn is_yaml_number(value: &str) -> bool {
+    if let Some(hex) = value.strip_prefix("0x") {
+        return !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit());
+    }
+    if let Some(octal) = value.strip_prefix("0o") {
+        return !octal.is_empty() && octal.chars().all(|c| ('0'..='7').contains(&c));
+    }
+
+    let unsigned = value.trim_start_matches(['-', '+']);
+    if value.len() - unsigned.len() > 1 {
+        return false;
+    }
+    if unsigned == ".inf" || unsigned == ".Inf" || unsigned == ".INF" {
+        return true;
+    }
+
+    let (mantissa, exponent) = match unsigned.find(['e', 'E']) {
+        Some(i) => (&unsigned[..i], Some(&unsigned[i + 1..])),
+        None => (unsigned, None),
+    };
+    let digits: String = mantissa.chars().filter(|c| *c != '.').collect();
+    let valid_mantissa = !digits.is_empty()
+        && digits.chars().all(|c| c.is_ascii_digit())
+        && mantissa.matches('.').count() <= 1;
+    let valid_exponent = match exponent {
+        Some(e) => {
+            let e = e.trim_start_matches(['-', '+']);
+            !e.is_empty() && e.chars().all(|c| c.is_ascii_digit())
+        }
+        None => true,
+    };
+
+    valid_mantissa && valid_exponent
+}
+
+struct YamlWriter {
+    output: String,
+    indent: usize,
+    newline: &'static str,
+    theme: Option<Theme>,
+    flow: bool,
+    /// Whether the output already ends with the line break of a literal block.
+    line_ended: bool,
+    anchor_names: Vec<String>,
+}
+
+impl YamlWriter {
+    fn new(
+        yaml_options: &YamlConverterOptions,
+        theme: &Theme,
+        anchor_names: Vec<String>,
+    ) -> YamlWriter {
+        YamlWriter {
+            output: String::new(),
+            indent: usize::from(yaml_options.indent.max(1)),
+            newline: yaml_options.newline,
+            theme: if yaml_options.color {
+                Some(theme.clone())
+            } else {
+                None
+            },
+            flow: false,
+            line_ended: false,
+            anchor_names,
+        }
+    }
+
+    fn write_block_document(&mut self, document: &Node) -> String {
+        self.output.clear();
+        self.line_ended = false;
+        match document {
+            Node::Mapping(mapping) if !mapping.items.is_empty() => {
+                self.write_anchor(mapping.anchor);
+                self.write_block_mapping(&mapping.items, 0, mapping.anchor == 0)
+            }
+            Node::Sequence(sequence) if !sequence.items.is_empty() => {
+                self.write_anchor(sequence.anchor);
+                self.write_block_sequence(&sequence.items, 0, sequence.anchor == 0)
+            }
+            _ => self.write_inline(document, 0, false),
+        }
+
+        self.output.clone()
+    }
+
+    fn write_flow_document(&mut self, document: &Node) -> String {
+        self.output.clear();
+        self.flow = true;
+        self.write_flow(document, false);
+        self.flow = false;
+        self.output.clone()
+    }
+
+    fn write_block_mapping(&mut self, items: &[(Node, Node)], column: usize, inline: bool) {
+        for (i, (key, value)) in items.iter().enumerate() {
+            if i > 0 || !inline {
+                self.start_line(column);
+            }
+            self.write_flow(key, true);
+            self.output.push(':');
+            self.write_block_value(value, column + self.indent, column + self.indent);
+        }
+    }
+
+    fn write_block_sequence(&mut self, items: &[Node], column: usize, inline: bool) {
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 || !inline {
+                self.start_line(column);
+            }
+            self.output.push('-');
+            match item {
+                Node::Mapping(mapping) if !mapping.items.is_empty() && mapping.anchor == 0 => {
+                    self.output.push(' ');
+                    self.write_block_mapping(&mapping.items, column + 2, true);
+                }
+                Node::Sequence(sequence) if !sequence.items.is_empty() && sequence.anchor == 0 => {
+                    self.output.push(' ');
+                    self.write_block_sequence(&sequence.items, column + 2, true);
+                }
+                _ => self.write_block_value(item, column + 2, column + self.indent),
+            }
+        }
+    }
+
+    fn write_block_value(&mut self, value: &Node, nested_column: usize, scalar_column: usize) {
+        match value {
+            Node::Mapping(mapping) if !value.is_empty_collection() => {
+                if mapping.anchor != 0 {
+                    self.output.push(' ');
+                    self.write_anchor(mapping.anchor);
+                }
+                self.write_block_mapping(&mapping.items, nested_column, false);
+            }
+            Node::Sequence(sequence) if !value.is_empty_collection() => {
+                if sequence.anchor != 0 {
+                    self.output.push(' ');
+                    self.write_anchor(sequence.anchor);
+                }
+                self.write_block_sequence(&sequence.items, nested_column, false);
+            }
+            _ => {
+                self.output.push(' ');
+                self.write_inline(value, scalar_column, false);
+            }
+        }
+    }
+
+    fn write_inline(&mut self, node: &Node, column: usize, is_key: bool) {
+        match node {
+            Node::Scalar(scalar) if is_literal_block(scalar) => {
+                self.write_scalar_properties(scalar);
+                self.write_literal_block(&scalar.value, column);
+            }
+            _ => self.write_flow(node, is_key),
+        }
+    }
+
+    fn write_literal_block(&mut self, value: &str, column: usize) {
+        let content = value.trim_end_matches('\n');
+        let chomping = match value.len() - content.len() {
+            0 => "|-",
+            1 => "|",
+            _ => "|+",
+        };
+        self.push_styled(chomping, TokenKind::String);
+
+        for line in content.split('\n') {
+            if line.is_empty() {
+                self.output.push_str(self.newline);
+            } else {
+                self.start_line(column);
+                self.push_styled(line, TokenKind::String);
+            }
+        }
+        for _ in 0..(value.len() - content.len()) {
+            self.output.push_str(self.newline);
+            self.line_ended = true;
+        }
+    }
+
+    fn write_flow(&mut self, node: &Node, is_key: bool) {
+        match node {
+            Node::Scalar(scalar) => {
+                self.write_scalar_properties(scalar);
+                let kind = if is_key {
+                    TokenKind::Key
+                } else if scalar.is_string() {
+                    TokenKind::String
+                } else {
+                    non_string_kind(&scalar.value)
+                };
+                let text = format_scalar(scalar, self.flow);
+                self.push_styled(&text, kind);
+            }
+            Node::Alias(anchor) => {
+                let alias = format!("*{}", self.anchor_name(*anchor));
+                self.output.push_str(&alias);
+            }
+            Node::Sequence(sequence) => {
+                self.write_anchor_prefix(sequence.anchor);
+                self.push_styled("[", TokenKind::Punctuation);
+                for (i, item) in sequence.items.iter().enumerate() {
+                    if i > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.write_flow(item, false);
+                }
+                self.push_styled("]", TokenKind::Punctuation);
+            }
+            Node::Mapping(mapping) => {
+                self.write_anchor_prefix(mapping.anchor);
+                self.push_styled("{", TokenKind::Punctuation);
+                for (i, (key, value)) in mapping.items.iter().enumerate() {
+                    if i > 0 {
+                        self.output.push_str(", ");
+                    }
+                    self.write_flow(key, true);
+                    self.output.push_str(": ");
+                    self.write_flow(value, false);
+                }
+                self.push_styled("}", TokenKind::Punctuation);
+            }
+        }
+    }
+
+    fn write_scalar_properties(&mut self, scalar: &Scalar) {
+        self.write_anchor_prefix(scalar.anchor);
+        if let Some(TokenType::Tag(handle, suffix)) = &scalar.tag {
+            self.output.push_str(&format_tag(handle, suffix));
+            self.output.push(' ');
+        }
+    }
+
+    fn write_anchor_prefix(&mut self, anchor: usize) {
+        if anchor != 0 {
+            self.write_anchor(anchor);
+            self.output.push(' ');
+        }
+    }
+
+    fn write_anchor(&mut self, anchor: usize) {
+        if anchor != 0 {
+            let anchor = format!("&{}", self.anchor_name(anchor));
+            self.output.push_str(&anchor);
+        }
+    }
+
+    fn anchor_name(&self, anchor: usize) -> String {
+        match self.anchor_names.get(anchor - 1) {
+            Some(name) => name.clone(),
+            None => format!("a{}", anchor),
+        }
+    }
+
+    fn start_line(&mut self, column: usize) {
+        if !self.output.is_empty() && !self.line_ended {
+            self.output.push_str(self.newline);
+        }
+        self.line_ended = false;
+        self.output.push_str(&" ".repeat(column));
+    }
+
+    fn push_styled(&mut self, text: &str, kind: TokenKind) {
+        match &self.theme {
+            Some(theme) => self.output.push_str(&theme.paint(kind, text)),
+            None => self.output.push_str(text),
+        }
+    }
+}
+
+fn is_literal_block(scalar: &Scalar) -> bool {
+    let block_style = scalar.style == TScalarStyle::Literal || scalar.style == TScalarStyle::Foled;
+    block_style
+        && scalar.value.trim_end_matches('\n').contains('\n')
+        && !scalar.value.starts_with(' ')
+        && !scalar.value.chars().any(|c| c.is_control() && c != '\n')
+}
+
+fn format_scalar(scalar: &Scalar, flow: bool) -> String {
+    if !scalar.is_string() {
+        return scalar.value.clone();
+    }
+
+    let simple = !scalar.value.chars().any(char::is_control);
+    let plain_allowed = !flow || is_flow_safe_plain(&scalar.value);
+    if scalar.style == TScalarStyle::SingleQuoted && simple {
+        format!("'{}'", scalar.value.replace('\'', "''"))
+    } else if scalar.style == TScalarStyle::Plain && simple && plain_allowed {
+        scalar.value.clone()
+    } else {
+        serde_json::to_string(&scalar.value).unwrap()
+    }
+}
+
+/// Writes a tag the way it could have been written. The scanner gives verbatim tags, like
+/// `!<tag:example.com,2000:a>`, an empty handle, and decodes `%` escapes in the suffix.
+fn format_tag(handle: &str, suffix: &str) -> String {
+    if handle.is_empty() && suffix != "!" {
+        format!("!<{}>", escape_tag_uri(suffix, true))
+    } else if handle.is_empty() {
+        String::from("!")
+    } else {
+        format!("{}{}", handle, escape_tag_uri(suffix, false))
+    }
+}
+
+fn escape_tag_uri(uri: &str, verbatim: bool) -> String {
+    const URI_CHARS: &str = "-;/?:@&=+$._~*'()";
+    const VERBATIM_CHARS: &str = ",[]!";
+
+    let mut escaped = String::new();
+    for c in uri.chars() {
+        if c.is_ascii_alphanumeric()
+            || URI_CHARS.contains(c)
+            || (verbatim && VERBATIM_CHARS.contains(c))
+        {
+            escaped.push(c);
+        } else {
+            let mut bytes = [0; 4];
+            for byte in c.encode_utf8(&mut bytes).bytes() {
+                escaped.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+    escaped
+}
+
+fn is_flow_safe_plain(value: &str) -> bool {
+    const INDICATORS: &str = "-?:,[]{}#&*!|>'\"%@`";
+
+    !value.is_empty()
+        && !value.starts_with(|c| INDICATORS.contains(c))
+        && !value.contains(|c| ",[]{}:".contains(c))
+        && !value.contains(" #")
+        && value.trim() == value
+}
+
+fn non_string_kind(value: &str) -> TokenKind {
+    match value {
+        "" | "~" | "null" | "Null" | "NULL" => TokenKind::Null,
+        "true" | "True" | "TRUE" | "false" | "False" | "FALSE" => TokenKind::Boolean,
+        _ => TokenKind::Number,
+    }
+}
+
+struct YamlConverterOptions {
+    indent: u8,
+    newline: &'static str,
+    color: bool,
+    drop_comments: bool,
+}
+
+impl Default for YamlConverterOptions {
+    fn default() -> YamlConverterOptions {
+        YamlConverterOptions {
+            indent: 2,
+            newline: "\n",
+            color: true,
+            drop_comments: false,
+        }
+    }
+}
+
+impl YamlConverterOptions {
+    pub fn from_options(converter_options: &HashMap<String, String>) -> YamlConverterOptions {
+        YamlConverterOptions {
+            indent: converter_utils::extract_u8(
+                INDENT_OPTION_NAME,
+                converter_options,
+                YamlConverterOptions::default().indent,
+            ),
+            newline: converter_utils::extract_newline(converter_options),
+            color: converter_utils::extract_bool(
+                COLOR_OPTION_NAME,
+                converter_options,
+                YamlConverterOptions::default().color,
+            ),
+            drop_comments: converter_utils::extract_bool(
+                DROP_COMMENTS_OPTION_NAME,
+                converter_options,
+                YamlConverterOptions::default().drop_comments,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::test_options;
+
+    #[test]
+    fn test_convert_invalid() {
+        let converter = YamlConverter::new();
+        let options = test_options(
+            "yaml",
+            false,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")],
+        );
+
+        assert!(matches!(
+            converter.prettify("key: [unclosed".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("a: b: c".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert!(matches!(
+            converter.prettify("".as_bytes(), &options),
+            Err(Error::CannotConvert { .. })
+        ));
+    }
+
+    #[test]
+    fn test_convert_scalar_only_unless_requested() {
+        let converter = YamlConverter::new();
+
+        assert!(matches!(
+            converter.prettify(
+                "just some text".as_bytes(),
+                &test_options(
+                    "all",
+                    false,
+                    &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")]
+                )
+            ),
+            Err(Error::CannotConvert { .. })
+        ));
+        assert_eq!(
+            converter.prettify(
+                "just some text".as_bytes(),
+                &test_options(
+                    "YAML",
+                    false,
+                    &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")]
+                )
+            ),
+            Ok(String::from("just some text"))
+        );
+    }
+
+    #[test]
+    fn test_convert_no_color() {
+        let converter = YamlConverter::new();
+        let options = test_options(
+            "all",
+            false,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")],
+        );
+        let input = r#"name:    'prettify'
+version: 0x1F
+tags: [json, "xml", 1.50]
+nested: {a: {b: ~}, empty: {}}
+people:
+    - name: alice
+      langs:
+          - rust
+    - [1, 2]
+script: |
+    echo hi
+    echo bye
+"#;
+
+        let output = converter.prettify(input.as_bytes(), &options).unwrap();
+        assert!(parse(&output).unwrap() == parse(input).unwrap());
+        assert_eq!(
+            output,
+            String::from(
+                r#"name: 'prettify'
+version: 0x1F
+tags:
+  - json
+  - "xml"
+  - 1.50
+nested:
+  a:
+    b: ~
+  empty: {}
+people:
+  - name: alice
+    langs:
+      - rust
+  - - 1
+    - 2
+script: |
+  echo hi
+  echo bye
+"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_convert_multiple_documents() {
+        let converter = YamlConverter::new();
+        let options = test_options(
+            "all",
+            false,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "4")],
+        );
+
+        assert_eq!(
+            converter.prettify(
+                "a:\n  b: 1\n---\n- x\n- &anchor y\n- *anchor\n".as_bytes(),
+                &options
+            ),
+            Ok(String::from(
+                "a:\n    b: 1\n---\n- x\n- &anchor y\n- *anchor"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_convert_keeps_anchor_names() {
+        let converter = YamlConverter::new();
+
+        assert_eq!(
+            converter.prettify(
+                "base: &base {x: 1}\nother:\n  <<: *base\n  list: &items [a, *base]\n---\nb: &base 2\nc: *base\n"
+                    .as_bytes(),
+                &test_options("all", false, &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")])
+            ),
+            Ok(String::from(
+                "base: &base\n  x: 1\nother:\n  <<: *base\n  list: &items\n    - a\n    - *base\n---\nb: &base 2\nc: *base"
+            ))
+        );
+        assert_eq!(
+            converter.prettify(
+                "base: &base {x: 1}\nother: *base\n".as_bytes(),
+                &test_options(
+                    "all",
+                    true,
+                    &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")]
+                )
+            ),
+            Ok(String::from("{base: &base {x: 1}, other: *base}"))
+        );
+    }
+
+    #[test]
+    fn test_convert_tags() {
+        let converter = YamlConverter::new();
+        let options = test_options(
+            "yaml",
+            false,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")],
+        );
+
+        assert_eq!(
+            converter.prettify(
+                "a: !!str 1\nb: !<tag:example.com,2000:x> y\nc: !e%21x%20y%2C z\nd: ! 2\n"
+                    .as_bytes(),
+                &options
+            ),
+            Ok(String::from(
+                "a: !!str 1\nb: !<tag:example.com,2000:x> y\nc: !e%21x%20y%2C z\nd: ! 2"
+            ))
+        );
+        assert_eq!(
+            converter.prettify("a: !!map {b: 1}\n".as_bytes(), &options),
+            Err(Error::cannot_convert(
+                CONVERTER_NAME,
+                "tags on mappings and sequences would be lost"
+            ))
+        );
+        assert_eq!(
+            converter.prettify("- !custom\n  - 1\n".as_bytes(), &options),
+            Err(Error::cannot_convert(
+                CONVERTER_NAME,
+                "tags on mappings and sequences would be lost"
+            ))
+        );
+        assert_eq!(
+            converter.prettify(
+                "%TAG !e! tag:example.com,2000:\n---\na: !e!x 1\n".as_bytes(),
+                &options
+            ),
+            Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                "%TAG directives would be lost",
+                Location {
+                    line: 1,
+                    column: 1,
+                    offset: 0
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_convert_comments() {
+        let converter = YamlConverter::new();
+        let input = "# settings\na: 1 # one\nb: \"not # a comment\"\nc: |\n  # text\n  more\n";
+
+        assert_eq!(
+            converter.prettify(
+                input.as_bytes(),
+                &test_options(
+                    "yaml",
+                    false,
+                    &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")]
+                )
+            ),
+            Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                "comments would be lost, set yaml.drop_comments=true to format it anyway",
+                Location {
+                    line: 1,
+                    column: 1,
+                    offset: 0
+                }
+            ))
+        );
+
+        let mut options = test_options(
+            "yaml",
+            false,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")],
+        );
+        options.converter_specific.insert(
+            String::from(DROP_COMMENTS_OPTION_NAME),
+            String::from("true"),
+        );
+        assert_eq!(
+            converter.prettify(input.as_bytes(), &options),
+            Ok(String::from(
+                "a: 1\nb: \"not # a comment\"\nc: |\n  # text\n  more\n"
+            ))
+        );
+
+        assert_eq!(
+            find_comment(
+                "a: \"x #\"\nb: |\n  #c\nd: e #f\n",
+                &parse("a: \"x #\"\nb: |\n  #c\nd: e #f\n").unwrap()
+            ),
+            Some(24)
+        );
+        assert_eq!(
+            find_comment(
+                "a: \"x #\"\nb: c#d\n",
+                &parse("a: \"x #\"\nb: c#d\n").unwrap()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_convert_literal_block_line_breaks() {
+        let converter = YamlConverter::new();
+        let options = test_options(
+            "yaml",
+            false,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")],
+        );
+
+        for input in [
+            "a: |+\n  x\n  u\n\n\nb: |-\n  y\n  z\nc: |+\n  v\n  w\n\n",
+            "- |\n  x\n  y\n---\n- |+\n  z\n  w\n\n",
+        ] {
+            let output = converter.prettify(input.as_bytes(), &options).unwrap();
+            assert_eq!(output, input);
+            assert!(parse(&output).unwrap() == parse(input).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_convert_color() {
+        let converter = YamlConverter::new();
+        let options = test_options(
+            "all",
+            false,
+            &[(COLOR_OPTION_NAME, "true"), (INDENT_OPTION_NAME, "2")],
+        );
+
+        assert_eq!(
+            converter.prettify("key: value\nnumber: 4".as_bytes(), &options),
+            Ok(String::from(
+                "\u{1b}[1;34mkey\u{1b}[0m: \u{1b}[32mvalue\u{1b}[0m\n\u{1b}[1;34mnumber\u{1b}[0m: 4"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_convert_reverse() {
+        let converter = YamlConverter::new();
+        let options = test_options(
+            "all",
+            true,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")],
+        );
+
+        assert_eq!(
+            converter.prettify(
+                r#"name: prettify
+enabled: true
+ratio: 1.50
+tags:
+  - has, comma
+  - "quoted"
+empty: []
+"#
+                .as_bytes(),
+                &options
+            ),
+            Ok(String::from(
+                r#"{name: prettify, enabled: true, ratio: 1.50, tags: ["has, comma", "quoted"], empty: []}"#
+            ))
+        );
+    }
+
+    #[test]
+    fn test_convert_round_trip_json() {
+        let converter = YamlConverter::new();
+
+        let yaml = converter
+            .prettify(
+                r#"{"a": [1, {"b": null}], "c": "d"}"#.as_bytes(),
+                &test_options(
+                    "yaml",
+                    false,
+                    &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")],
+                ),
+            )
+            .unwrap();
+        assert_eq!(yaml, "\"a\":\n  - 1\n  - \"b\": null\n\"c\": \"d\"");
+        assert_eq!(
+            converter.prettify(
+                yaml.as_bytes(),
+                &test_options(
+                    "yaml",
+                    true,
+                    &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")]
+                )
+            ),
+            Ok(String::from(r#"{"a": [1, {"b": null}], "c": "d"}"#))
+        );
+    }
+
+    #[test]
+    fn test_convert_error_location() {
+        let converter = YamlConverter::new();
+        let options = test_options(
+            "yaml",
+            false,
+            &[(COLOR_OPTION_NAME, "false"), (INDENT_OPTION_NAME, "2")],
+        );
+
+        assert_eq!(
+            converter.prettify("a: 1\nb: c: d".as_bytes(), &options),
+            Err(Error::cannot_convert_at(
+                CONVERTER_NAME,
+                "mapping values are not allowed in this context",
+                Location {
+                    line: 2,
+                    column: 5,
+                    offset: 9
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_detect() {
+        let converter = YamlConverter::new();
+
+        assert_eq!(converter.detect("a: 1\nb:\n  - x\n".as_bytes()), 70);
+        assert_eq!(converter.detect("{a: 1, b: [x]}".as_bytes()), 40);
+        assert_eq!(converter.detect("just text".as_bytes()), 0);
+        assert_eq!(converter.detect("a: [1".as_bytes()), 0);
+    }
+}
@@ -0,0 +1,203 @@
+const CONTEXT_LINES: usize = 3;
+const MAX_TABLE_SIZE: usize = 4_000_000;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Change {
+    Same,
+    Removed,
+    Added,
+}
+
+/// Builds a unified diff between two texts, like `diff -u` prints. Returns an empty string when
+/// the texts have the same lines.
+pub fn unified_diff(old: &str, new: &str, old_name: &str, new_name: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let changes = diff_lines(&old_lines, &new_lines);
+    if changes.iter().all(|change| *change == Change::Same) {
+        return String::new();
+    }
+
+    let mut output = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for (start, end) in hunk_ranges(&changes) {
+        let (mut old_line, mut new_line) = line_numbers_at(&changes, start);
+        let mut body = String::new();
+        let (old_start, new_start) = (old_line, new_line);
+        for change in &changes[start..end] {
+            match change {
+                Change::Same => {
+                    body.push_str(&format!(" {}\n", old_lines[old_line]));
+                    old_line += 1;
+                    new_line += 1;
+                }
+                Change::Removed => {
+                    body.push_str(&format!("-{}\n", old_lines[old_line]));
+                    old_line += 1;
+                }
+                Change::Added => {
+                    body.push_str(&format!("+{}\n", new_lines[new_line]));
+                    new_line += 1;
+                }
+            }
+        }
+
+        output.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_start, old_line - old_start),
+            hunk_range(new_start, new_line - new_start)
+        ));
+        output.push_str(&body);
+    }
+
+    output
+}
+
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<Change> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let mut changes = vec![Change::Same; prefix];
+    if (old_middle.len() + 1) * (new_middle.len() + 1) > MAX_TABLE_SIZE {
+        changes.extend(vec![Change::Removed; old_middle.len()]);
+        changes.extend(vec![Change::Added; new_middle.len()]);
+    } else {
+        changes.extend(longest_common_subsequence(old_middle, new_middle));
+    }
+    changes.extend(vec![Change::Same; suffix]);
+
+    changes
+}
+
+fn longest_common_subsequence(old: &[&str], new: &[&str]) -> Vec<Change> {
+    let width = new.len() + 1;
+    let mut lengths = vec![0usize; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut changes = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            changes.push(Change::Same);
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            changes.push(Change::Removed);
+            i += 1;
+        } else {
+            changes.push(Change::Added);
+            j += 1;
+        }
+    }
+    changes.extend(vec![Change::Removed; old.len() - i]);
+    changes.extend(vec![Change::Added; new.len() - j]);
+
+    changes
+}
+
+fn hunk_ranges(changes: &[Change]) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (i, change) in changes.iter().enumerate() {
+        if *change == Change::Same {
+            continue;
+        }
+
+        let start = i.saturating_sub(CONTEXT_LINES);
+        let end = (i + 1 + CONTEXT_LINES).min(changes.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+
+    ranges
+}
+
+fn line_numbers_at(changes: &[Change], index: usize) -> (usize, usize) {
+    changes[..index]
+        .iter()
+        .fold((0, 0), |(old, new), change| match change {
+            Change::Same => (old + 1, new + 1),
+            Change::Removed => (old + 1, new),
+            Change::Added => (old, new + 1),
+        })
+}
+
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff_same() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb", "old", "new"), "");
+    }
+
+    #[test]
+    fn test_unified_diff_changes() {
+        assert_eq!(
+            unified_diff(
+                "{\"a\":1}",
+                "{\n    \"a\": 1\n}",
+                "a.json",
+                "a.json (formatted)"
+            ),
+            "--- a.json
++++ a.json (formatted)
+@@ -1 +1,3 @@
+-{\"a\":1}
++{
++    \"a\": 1
++}
+"
+        );
+        assert_eq!(
+            unified_diff(
+                "1\n2\n3\n4\nfive\n6\n7\n8\n9\n10\n11\n12\n13\nfourteen",
+                "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n11\n12\n13\n14\n15",
+                "old",
+                "new"
+            ),
+            "--- old
++++ new
+@@ -2,7 +2,7 @@
+ 2
+ 3
+ 4
+-five
++5
+ 6
+ 7
+ 8
+@@ -11,4 +11,5 @@
+ 11
+ 12
+ 13
+-fourteen
++14
++15
+"
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use crate::converters;
+use globset::{GlobBuilder, GlobMatcher};
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+pub const EDITORCONFIG_NAME: &str = ".editorconfig";
+
+/// The `.editorconfig` properties prettify reads, with the converter options they set.
+const PROPERTIES: [(&str, &str); 4] = [
+    ("indent_style", "indent_style"),
+    ("indent_size", "indent"),
+    ("end_of_line", "end_of_line"),
+    ("insert_final_newline", "insert_final_newline"),
+];
+
+/// One `.editorconfig` file, with the properties of each section in the order they are written.
+struct EditorConfig {
+    root: bool,
+    sections: Vec<(GlobMatcher, Vec<(String, String)>)>,
+}
+
+/// The converter options the `.editorconfig` files in the directory of the file and the ones above
+/// it set for the file. Files closer to it take precedence, and the search stops at a file with
+/// `root = true`. Values the converters do not accept are ignored.
+pub fn options_for(path: &Path) -> Result<HashMap<String, String>, String> {
+    let path = if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        env::current_dir().map_err(|e| e.to_string())?.join(path)
+    };
+
+    let mut configs = Vec::new();
+    for dir in path.ancestors().skip(1) {
+        let config_path = dir.join(EDITORCONFIG_NAME);
+        if !config_path.is_file() {
+            continue;
+        }
+        let config = EditorConfig::read(&config_path)?;
+        let root = config.root;
+        configs.push(config);
+        if root {
+            break;
+        }
+    }
+
+    let mut options = HashMap::new();
+    for config in configs.iter().rev() {
+        for (glob, section) in &config.sections {
+            if !glob.is_match(&path) {
+                continue;
+            }
+            for (property, value) in section {
+                let option = match PROPERTIES.iter().find(|(p, _)| p == property) {
+                    Some((_, option)) => *option,
+                    None => continue,
+                };
+                if value == "unset" {
+                    options.remove(option);
+                } else if is_valid(option, value) {
+                    options.insert(String::from(option), value.clone());
+                }
+            }
+        }
+    }
+    Ok(options)
+}
+
+impl EditorConfig {
+    fn read(path: &Path) -> Result<EditorConfig, String> {
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Could not read '{}': {}", path.display(), e))?;
+        Ok(EditorConfig::parse(
+            &text,
+            path.parent().unwrap_or(Path::new("")),
+        ))
+    }
+
+    /// Parses the INI-like format leniently, skipping lines and sections it does not understand
+    /// like other `.editorconfig` readers do.
+    fn parse(text: &str, dir: &Path) -> EditorConfig {
+        let mut config = EditorConfig {
+            root: false,
+            sections: Vec::new(),
+        };
+        let mut section: Option<(GlobMatcher, Vec<(String, String)>)> = None;
+        let mut in_preamble = true;
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                continue;
+            }
+            if let Some(glob) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                config.sections.extend(section.take());
+                section = section_matcher(glob, dir).map(|glob| (glob, Vec::new()));
+                in_preamble = false;
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim().to_lowercase(), value.trim().to_lowercase()),
+                None => continue,
+            };
+            if in_preamble {
+                if key == "root" {
+                    config.root = value == "true";
+                }
+            } else if let Some((_, properties)) = section.as_mut() {
+                properties.push((key, value));
+            }
+        }
+        config.sections.extend(section);
+
+        config
+    }
+}
+
+/// Section globs with a `/` are relative to the directory of the `.editorconfig` file, and others
+/// match file names in it or any directory below it.
+fn section_matcher(glob: &str, dir: &Path) -> Option<GlobMatcher> {
+    let dir = globset::escape(&dir.to_string_lossy());
+    let pattern = if glob.contains('/') {
+        format!("{}/{}", dir, glob.trim_start_matches('/'))
+    } else {
+        format!("{}/**/{}", dir, glob)
+    };
+    GlobBuilder::new(&pattern)
+        .literal_separator(true)
+        .build()
+        .ok()
+        .map(|glob| glob.compile_matcher())
+}
+
+fn is_valid(option: &str, value: &str) -> bool {
+    converters::get_converters()
+        .iter()
+        .flat_map(|converter| converter.options())
+        .filter(|schema| schema.name == option)
+        .all(|schema| schema.validate(value).is_ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::process;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("prettify-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("project/data/nested")).unwrap();
+        dir
+    }
+
+    fn map(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_options_for() {
+        let dir = test_dir("editorconfig");
+        fs::write(
+            dir.join(EDITORCONFIG_NAME),
+            "root = true\n[*]\nindent_style = tab\nend_of_line = crlf\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.join("project").join(EDITORCONFIG_NAME),
+            concat!(
+                "# top-most\n",
+                "root = true\n\n",
+                "[*]\n",
+                "indent_style = space\n",
+                "indent_size = 4\n",
+                "insert_final_newline = true\n\n",
+                "[*.{json,yml}]\n",
+                "indent_size = 2\n\n",
+                "[data/*.xml]\n",
+                "indent_style = tab\n",
+                "indent_size = 40\n",
+                "insert_final_newline = unset\n",
+                "charset = utf-8\n",
+            ),
+        )
+        .unwrap();
+        let project = dir.join("project");
+
+        assert_eq!(
+            options_for(&project.join("data/nested/a.json")).unwrap(),
+            map(&[
+                ("indent_style", "space"),
+                ("indent", "2"),
+                ("insert_final_newline", "true"),
+            ])
+        );
+        assert_eq!(
+            options_for(&project.join("data/b.xml")).unwrap(),
+            map(&[("indent_style", "tab"), ("indent", "4")])
+        );
+        assert_eq!(
+            options_for(&project.join("data/nested/c.xml")).unwrap(),
+            map(&[
+                ("indent_style", "space"),
+                ("indent", "4"),
+                ("insert_final_newline", "true"),
+            ])
+        );
+
+        fs::write(
+            dir.join("project").join(EDITORCONFIG_NAME),
+            "[*.json]\nindent_size = 3\n",
+        )
+        .unwrap();
+        assert_eq!(
+            options_for(&project.join("d.json")).unwrap(),
+            map(&[
+                ("indent_style", "tab"),
+                ("indent", "3"),
+                ("end_of_line", "crlf"),
+            ])
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,4 +1,268 @@
-#[derive(Debug, PartialEq)]
+use std::fmt;
+
+/// Where in the input a conversion failed. Lines and columns start at 1, the offset is in bytes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+    pub offset: usize,
+}
+
+impl Location {
+    pub fn from_offset(input: &str, offset: usize) -> Location {
+        let mut offset = offset.min(input.len());
+        while !input.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let before = &input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Location {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            offset,
+        }
+    }
+
+    pub fn from_line_column(input: &str, line: usize, column: usize) -> Location {
+        let line_start: usize = input
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(str::len)
+            .sum();
+        let column_offset: usize = input[line_start..]
+            .chars()
+            .take_while(|c| *c != '\n')
+            .take(column.saturating_sub(1))
+            .map(char::len_utf8)
+            .sum();
+
+        Location::from_offset(input, line_start + column_offset)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub enum Error {
-    CannotConvert,
+    CannotConvert {
+        converter: String,
+        message: String,
+        location: Option<Location>,
+    },
+    NoConverterMatched(Vec<Error>),
+    UnknownInputType(String),
+    InvalidOption {
+        option: String,
+        value: String,
+        expected: String,
+    },
+    UnknownOption {
+        converter: String,
+        option: String,
+        known: Vec<String>,
+    },
+    StageFailed {
+        stage: usize,
+        input_type: String,
+        input: Vec<u8>,
+        cause: Box<Error>,
+    },
+}
+
+impl Error {
+    pub fn cannot_convert(converter: &str, message: &str) -> Error {
+        Error::CannotConvert {
+            converter: String::from(converter),
+            message: String::from(message),
+            location: None,
+        }
+    }
+
+    pub fn cannot_convert_at(converter: &str, message: &str, location: Location) -> Error {
+        Error::CannotConvert {
+            converter: String::from(converter),
+            message: String::from(message),
+            location: Some(location),
+        }
+    }
+
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            Error::CannotConvert { location, .. } => *location,
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::CannotConvert {
+                converter,
+                message,
+                location: Some(location),
+            } => write!(
+                f,
+                "{}: {} at line {} column {}",
+                converter, message, location.line, location.column
+            ),
+            Error::CannotConvert {
+                converter,
+                message,
+                location: None,
+            } => write!(f, "{}: {}", converter, message),
+            Error::NoConverterMatched(_) => write!(f, "Input was not in any recognized format."),
+            Error::UnknownInputType(input_type) => {
+                write!(f, "'{}' is not a supported type.", input_type)
+            }
+            Error::InvalidOption {
+                option,
+                value,
+                expected,
+            } => write!(
+                f,
+                "'{}' is not a valid value for the {} option, it must be {}.",
+                value, option, expected
+            ),
+            Error::UnknownOption {
+                converter,
+                option,
+                known,
+            } => write!(
+                f,
+                "{} does not have an option named '{}'. Its options are: {}.",
+                converter,
+                option,
+                known.join(", ")
+            ),
+            Error::StageFailed {
+                stage, input_type, ..
+            } => write!(
+                f,
+                "Stage {} of the pipeline ({}) failed.",
+                stage, input_type
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_location_from_offset() {
+        let input = "first\nsécond\nthird";
+
+        assert_eq!(
+            Location::from_offset(input, 0),
+            Location {
+                line: 1,
+                column: 1,
+                offset: 0
+            }
+        );
+        assert_eq!(
+            Location::from_offset(input, 9),
+            Location {
+                line: 2,
+                column: 3,
+                offset: 9
+            }
+        );
+        assert_eq!(
+            Location::from_offset(input, 8),
+            Location {
+                line: 2,
+                column: 2,
+                offset: 7
+            }
+        );
+        assert_eq!(
+            Location::from_offset(input, 100),
+            Location {
+                line: 3,
+                column: 6,
+                offset: 19
+            }
+        );
+    }
+
+    #[test]
+    fn test_location_from_line_column() {
+        let input = "first\nsécond\nthird";
+
+        assert_eq!(
+            Location::from_line_column(input, 2, 3),
+            Location {
+                line: 2,
+                column: 3,
+                offset: 9
+            }
+        );
+        assert_eq!(
+            Location::from_line_column(input, 1, 0),
+            Location {
+                line: 1,
+                column: 1,
+                offset: 0
+            }
+        );
+        assert_eq!(
+            Location::from_line_column(input, 1, 50),
+            Location {
+                line: 1,
+                column: 6,
+                offset: 5
+            }
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            Error::cannot_convert("JSON", "expected value").to_string(),
+            "JSON: expected value"
+        );
+        assert_eq!(
+            Error::cannot_convert_at(
+                "XML",
+                "unclosed element",
+                Location {
+                    line: 3,
+                    column: 7,
+                    offset: 20
+                }
+            )
+            .to_string(),
+            "XML: unclosed element at line 3 column 7"
+        );
+        assert_eq!(
+            Error::StageFailed {
+                stage: 2,
+                input_type: String::from("json"),
+                input: b"test".to_vec(),
+                cause: Box::new(Error::cannot_convert("JSON", "expected value")),
+            }
+            .to_string(),
+            "Stage 2 of the pipeline (json) failed."
+        );
+        assert_eq!(
+            Error::InvalidOption {
+                option: String::from("indent"),
+                value: String::from("four"),
+                expected: String::from("a number from 0 to 16"),
+            }
+            .to_string(),
+            "'four' is not a valid value for the indent option, it must be a number from 0 to 16."
+        );
+        assert_eq!(
+            Error::UnknownOption {
+                converter: String::from("CSV"),
+                option: String::from("indent"),
+                known: vec![String::from("separator"), String::from("spacing")],
+            }
+            .to_string(),
+            "CSV does not have an option named 'indent'. Its options are: separator, spacing."
+        );
+    }
 }
@@ -0,0 +1,221 @@
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::WalkBuilder;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+const EXTENSION_TYPES: [(&str, &str); 11] = [
+    ("json", "JSON"),
+    ("xml", "XML"),
+    ("svg", "XML"),
+    ("xsd", "XML"),
+    ("xsl", "XML"),
+    ("yaml", "YAML"),
+    ("yml", "YAML"),
+    ("csv", "CSV"),
+    ("tsv", "CSV"),
+    ("b64", "Base64"),
+    ("base64", "Base64"),
+];
+
+/// The converter to use for a file based on its extension, if it is one we know.
+pub fn input_type_for_path(path: &Path) -> Option<&'static str> {
+    let extension = path.extension()?.to_str()?;
+    EXTENSION_TYPES
+        .iter()
+        .find(|(known, _)| known.eq_ignore_ascii_case(extension))
+        .map(|(_, input_type)| *input_type)
+}
+
+pub fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Expands files, directories and glob patterns into the list of files to format. Directories are
+/// walked recursively, skipping hidden files and anything a `.gitignore` excludes, and only files
+/// with a known extension are picked from them. Excludes use `.gitignore` syntax. A file named by
+/// more than one of the paths is listed once, where it is first found.
+pub fn find_files(paths: &[&str], excludes: &[&str]) -> Result<Vec<PathBuf>, String> {
+    let excludes = build_excludes(excludes)?;
+    let mut files = Vec::new();
+    for path in paths {
+        if is_glob(path) {
+            let matches = glob::glob(path)
+                .map_err(|e| format!("'{}' is not a valid pattern: {}", path, e))?;
+            for matched in matches {
+                let matched = matched.map_err(|e| e.to_string())?;
+                add_path(&matched, &excludes, &mut files)?;
+            }
+        } else {
+            let path = Path::new(path);
+            if !path.exists() {
+                return Err(format!("'{}' does not exist", path.display()));
+            }
+            add_path(path, &excludes, &mut files)?;
+        }
+    }
+
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(file.canonicalize().unwrap_or_else(|_| file.clone())));
+    Ok(files)
+}
+
+fn add_path(path: &Path, excludes: &Gitignore, files: &mut Vec<PathBuf>) -> Result<(), String> {
+    if is_excluded(path, path.is_dir(), excludes) {
+        return Ok(());
+    }
+    if !path.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    let filter_excludes = excludes.clone();
+    let walker = WalkBuilder::new(path)
+        .require_git(false)
+        .sort_by_file_name(|a, b| a.cmp(b))
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            !is_excluded(entry.path(), is_dir, &filter_excludes)
+        })
+        .build();
+    for entry in walker {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let is_file = entry.file_type().is_some_and(|t| t.is_file());
+        if is_file && input_type_for_path(entry.path()).is_some() {
+            files.push(entry.into_path());
+        }
+    }
+
+    Ok(())
+}
+
+fn build_excludes(excludes: &[&str]) -> Result<Gitignore, String> {
+    let mut builder = GitignoreBuilder::new("");
+    for exclude in excludes {
+        builder
+            .add_line(None, exclude)
+            .map_err(|e| format!("'{}' is not a valid exclude pattern: {}", exclude, e))?;
+    }
+    builder.build().map_err(|e| e.to_string())
+}
+
+fn is_excluded(path: &Path, is_dir: bool, excludes: &Gitignore) -> bool {
+    excludes.matched(path, is_dir).is_ignore()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::process;
+
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("prettify-{}-{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for sub_dir in ["config", "config/nested", "build", ".hidden"] {
+            fs::create_dir_all(dir.join(sub_dir)).unwrap();
+        }
+        for file in [
+            "config/a.json",
+            "config/b.yml",
+            "config/notes.txt",
+            "config/nested/c.xml",
+            "config/nested/d.min.json",
+            "build/e.json",
+            ".hidden/f.json",
+        ] {
+            fs::write(dir.join(file), "{}").unwrap();
+        }
+        fs::write(dir.join(".gitignore"), "build/\n").unwrap();
+        dir
+    }
+
+    fn relative(dir: &Path, files: Vec<PathBuf>) -> Vec<String> {
+        files
+            .iter()
+            .map(|f| {
+                f.strip_prefix(dir)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_input_type_for_path() {
+        assert_eq!(input_type_for_path(Path::new("a/b.JSON")), Some("JSON"));
+        assert_eq!(input_type_for_path(Path::new("c.yml")), Some("YAML"));
+        assert_eq!(input_type_for_path(Path::new("d.txt")), None);
+        assert_eq!(input_type_for_path(Path::new("Makefile")), None);
+    }
+
+    #[test]
+    fn test_find_files_in_directory() {
+        let dir = test_dir("find-directory");
+        let root = dir.to_str().unwrap();
+
+        assert_eq!(
+            relative(&dir, find_files(&[root], &[]).unwrap()),
+            vec![
+                "config/a.json",
+                "config/b.yml",
+                "config/nested/c.xml",
+                "config/nested/d.min.json",
+            ]
+        );
+        assert_eq!(
+            relative(&dir, find_files(&[root], &["*.min.json", "b.yml"]).unwrap()),
+            vec!["config/a.json", "config/nested/c.xml"]
+        );
+        assert_eq!(
+            relative(&dir, find_files(&[root], &["nested/"]).unwrap()),
+            vec!["config/a.json", "config/b.yml"]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_find_files_from_globs_and_files() {
+        let dir = test_dir("find-globs");
+        let pattern = format!("{}/config/**/*.json", dir.to_str().unwrap());
+        let text_file = dir.join("config/notes.txt");
+
+        assert_eq!(
+            relative(
+                &dir,
+                find_files(&[&pattern, text_file.to_str().unwrap()], &[]).unwrap()
+            ),
+            vec![
+                "config/a.json",
+                "config/nested/d.min.json",
+                "config/notes.txt"
+            ]
+        );
+        assert!(find_files(&[dir.join("missing.json").to_str().unwrap()], &[]).is_err());
+
+        let config = dir.join("config");
+        let nested_file = dir.join("config/./nested/c.xml");
+        assert_eq!(
+            relative(
+                &dir,
+                find_files(
+                    &[
+                        nested_file.to_str().unwrap(),
+                        config.to_str().unwrap(),
+                        &pattern,
+                    ],
+                    &[]
+                )
+                .unwrap()
+            ),
+            vec![
+                "config/./nested/c.xml",
+                "config/a.json",
+                "config/b.yml",
+                "config/nested/d.min.json",
+            ]
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,287 @@
+use crate::error::Error;
+use crate::options::Options;
+use crate::theme::{Theme, TokenKind};
+use colored_json::{Color, Style};
+use std::collections::HashMap;
+
+/// The class of the `pre` element holding the output. Tokens get a class made of this and the
+/// token kind, like `prettify-key`.
+const CLASS: &str = "prettify";
+
+/// The first 16 colors of the terminal palette, as xterm shows them.
+const BASIC_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+/// Prettifies the input like `prettify`, but marks up the colored tokens with HTML instead of
+/// terminal escapes. A fragment is a `pre` element with a CSS class on each token, and a standalone
+/// page adds a stylesheet built from the theme in the options.
+pub fn prettify_html(input: &[u8], options: &Options, standalone: bool) -> Result<String, Error> {
+    let mut classifying = options.clone();
+    classifying.theme = classifying_theme();
+    classifying.set_for_all("color", "true");
+    let colored = crate::prettify(input, &classifying)?;
+    let fragment = to_html(&colored);
+
+    if !standalone {
+        return Ok(fragment);
+    }
+    Ok(format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<style>\n{}</style>\n</head>\n<body>\n{}\n</body>\n</html>",
+        stylesheet(&options.theme),
+        fragment
+    ))
+}
+
+/// The CSS rules that give each token kind its style from the theme.
+pub fn stylesheet(theme: &Theme) -> String {
+    let (foreground, background) = if theme.light_background() {
+        ("#000000", "#ffffff")
+    } else {
+        ("#d4d4d4", "#1e1e1e")
+    };
+    let mut css = format!(
+        "pre.{} {{ color: {}; background-color: {}; padding: 1em; }}\n",
+        CLASS, foreground, background
+    );
+    for kind in TokenKind::ALL {
+        let declarations = declarations(theme.style(kind));
+        if !declarations.is_empty() {
+            css.push_str(&format!(
+                ".{}-{} {{ {} }}\n",
+                CLASS,
+                kind.name(),
+                declarations.join(" ")
+            ));
+        }
+    }
+    css
+}
+
+/// A theme giving every token kind its own color, so the kind of each colored token can be told
+/// from its escape code.
+fn classifying_theme() -> Theme {
+    let mut styles = HashMap::new();
+    for (i, kind) in TokenKind::ALL.iter().enumerate() {
+        styles.insert(String::from(kind.name()), i.to_string());
+    }
+    Theme::custom("html", &Theme::default(), &styles).unwrap()
+}
+
+/// Turns output colored with the classifying theme into HTML, wrapping each colored token in a
+/// `span` with the class of its kind.
+fn to_html(colored: &str) -> String {
+    let mut html = format!("<pre class=\"{}\">", CLASS);
+    for (kind, text) in segments(colored) {
+        let kind = match kind {
+            Some(kind) => kind,
+            None => {
+                push_escaped(&mut html, &text);
+                continue;
+            }
+        };
+        // Formatters color the line break and indentation before a closing bracket too, so each
+        // line gets its own span without the whitespace around it.
+        for line in text.split_inclusive('\n') {
+            let token = line.trim();
+            let leading = &line[..line.len() - line.trim_start().len()];
+            push_escaped(&mut html, leading);
+            if !token.is_empty() {
+                html.push_str(&format!("<span class=\"{}-{}\">", CLASS, kind.name()));
+                push_escaped(&mut html, token);
+                html.push_str("</span>");
+            }
+            push_escaped(&mut html, &line[leading.len() + token.len()..]);
+        }
+    }
+    html.push_str("</pre>");
+    html
+}
+
+/// Splits colored output into runs of text with the token kind they were colored as, joining runs
+/// of the same kind.
+fn segments(colored: &str) -> Vec<(Option<TokenKind>, String)> {
+    let theme = classifying_theme();
+    let kinds: HashMap<String, TokenKind> = TokenKind::ALL
+        .iter()
+        .map(|kind| (theme.style(*kind).prefix().to_string(), *kind))
+        .collect();
+
+    let mut segments: Vec<(Option<TokenKind>, String)> = Vec::new();
+    let mut kind = None;
+    for (i, part) in colored.split('\u{1b}').enumerate() {
+        let text = if i == 0 {
+            part
+        } else {
+            let end = part.find('m').map_or(0, |end| end + 1);
+            kind = kinds.get(&format!("\u{1b}{}", &part[..end])).copied();
+            &part[end..]
+        };
+        if text.is_empty() {
+            continue;
+        }
+        match segments.last_mut() {
+            Some((last_kind, last_text)) if *last_kind == kind => last_text.push_str(text),
+            _ => segments.push((kind, String::from(text))),
+        }
+    }
+    segments
+}
+
+fn push_escaped(html: &mut String, text: &str) {
+    for c in text.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            _ => html.push(c),
+        }
+    }
+}
+
+fn declarations(style: Style) -> Vec<String> {
+    let mut declarations = Vec::new();
+    if let Some(color) = style.foreground {
+        declarations.push(format!("color: {};", css_color(color)));
+    }
+    if let Some(color) = style.background {
+        declarations.push(format!("background-color: {};", css_color(color)));
+    }
+    if style.is_bold {
+        declarations.push(String::from("font-weight: bold;"));
+    }
+    if style.is_dimmed {
+        declarations.push(String::from("opacity: 0.7;"));
+    }
+    if style.is_italic {
+        declarations.push(String::from("font-style: italic;"));
+    }
+    if style.is_underline {
+        declarations.push(String::from("text-decoration: underline;"));
+    }
+    declarations
+}
+
+fn css_color(color: Color) -> String {
+    let (r, g, b) = match color {
+        Color::Black => BASIC_COLORS[0],
+        Color::Red => BASIC_COLORS[1],
+        Color::Green => BASIC_COLORS[2],
+        Color::Yellow => BASIC_COLORS[3],
+        Color::Blue => BASIC_COLORS[4],
+        Color::Purple => BASIC_COLORS[5],
+        Color::Cyan => BASIC_COLORS[6],
+        Color::White => BASIC_COLORS[7],
+        Color::Fixed(i) => palette_color(i),
+        Color::RGB(r, g, b) => (r, g, b),
+    };
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// The RGB value of a color from the 256 color palette: the 16 basic colors, a 6x6x6 color cube
+/// and 24 shades of gray.
+fn palette_color(i: u8) -> (u8, u8, u8) {
+    match i {
+        0..=15 => BASIC_COLORS[i as usize],
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + 40 * v };
+            let i = i - 16;
+            (level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        _ => {
+            let gray = 8 + 10 * (i - 232);
+            (gray, gray, gray)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::test_options;
+
+    #[test]
+    fn test_json_fragment() {
+        assert_eq!(
+            prettify_html(
+                "{\"a\":[1,true,null]}".as_bytes(),
+                &test_options("json", false, &[("indent", "2")]),
+                false
+            ),
+            Ok(String::from(concat!(
+                "<pre class=\"prettify\">",
+                "<span class=\"prettify-punctuation\">{</span>\n",
+                "  <span class=\"prettify-key\">&quot;a&quot;</span>: ",
+                "<span class=\"prettify-punctuation\">[</span>\n",
+                "    <span class=\"prettify-number\">1</span>,\n",
+                "    <span class=\"prettify-boolean\">true</span>,\n",
+                "    <span class=\"prettify-null\">null</span>\n",
+                "  <span class=\"prettify-punctuation\">]</span>\n",
+                "<span class=\"prettify-punctuation\">}</span>",
+                "</pre>"
+            )))
+        );
+    }
+
+    #[test]
+    fn test_xml_fragment_is_escaped() {
+        let html = prettify_html(
+            "<a b=\"&amp;\">x</a>".as_bytes(),
+            &test_options("xml", false, &[("indent", "2")]),
+            false,
+        )
+        .unwrap();
+
+        assert!(!html.contains('\u{1b}'));
+        assert!(html.contains("<span class=\"prettify-tag\">&lt;a</span>"));
+        assert!(html.contains("&amp;amp;"));
+    }
+
+    #[test]
+    fn test_standalone_page() {
+        let html = prettify_html(
+            "[1]".as_bytes(),
+            &test_options("json", false, &[("indent", "2")]),
+            true,
+        )
+        .unwrap();
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains(".prettify-key { color: #0000ee; font-weight: bold; }"));
+        assert!(html.contains("<pre class=\"prettify\">"));
+        assert!(html.ends_with("</html>"));
+    }
+
+    #[test]
+    fn test_stylesheet() {
+        let css = stylesheet(&Theme::built_in("light").unwrap());
+
+        assert!(css.starts_with("pre.prettify { color: #000000; background-color: #ffffff;"));
+        assert!(css.contains(".prettify-comment { opacity: 0.7; font-style: italic; }"));
+        assert!(!stylesheet(&Theme::default()).contains(".prettify-number"));
+    }
+
+    #[test]
+    fn test_palette_color() {
+        assert_eq!(css_color(Color::Fixed(9)), "#ff0000");
+        assert_eq!(css_color(Color::Fixed(208)), "#ff8700");
+        assert_eq!(css_color(Color::Fixed(244)), "#808080");
+        assert_eq!(css_color(Color::RGB(1, 2, 255)), "#0102ff");
+    }
+}
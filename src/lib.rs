@@ -1,39 +1,505 @@
+pub mod atomic_file;
+pub mod color;
+pub mod config;
 mod converter_utils;
 pub mod converters;
+pub mod diff;
+pub mod editorconfig;
 pub mod error;
+pub mod files;
+pub mod html;
 pub mod options;
+pub mod parallel;
+mod pipeline;
+mod recursive;
+pub mod text;
+pub mod theme;
 
+use self::converters::Converter;
+use self::error::Error;
 use self::options::Options;
+use std::cmp::Reverse;
 
-pub fn prettify_default(input: &String) -> Result<String, String> {
+pub fn prettify_default(input: &[u8]) -> Result<String, Error> {
     prettify(input, &Options::default())
 }
 
-pub fn prettify(input: &str, options: &Options) -> Result<String, String> {
-    for converter in converters::get_converters() {
-        if should_try_converter(&options.input_type, converter.name()) {
-            if options.verbose {
-                println!("Trying to convert as {}", converter.name())
+pub fn prettify(input: &[u8], options: &Options) -> Result<String, Error> {
+    Prettifier::new(options)?.prettify(input)
+}
+
+/// Prettifies many inputs with the same options, like the lines of `--lines`. The options are
+/// checked and the converter for a fixed input type is looked up once instead of for every input.
+pub struct Prettifier<'a> {
+    options: &'a Options,
+    converter: Option<Box<dyn Converter>>,
+}
+
+impl<'a> Prettifier<'a> {
+    pub fn new(options: &'a Options) -> Result<Prettifier<'a>, Error> {
+        validate_options(options)?;
+        let recursive = options.recursion_depth > 0 && !options.reverse;
+        let converts = options.pipeline.is_empty()
+            && !recursive
+            && !options.input_type.eq_ignore_ascii_case("all");
+        let converter = if converts {
+            let converter = converters::get_converters()
+                .into_iter()
+                .find(|c| options.input_type.eq_ignore_ascii_case(c.name()))
+                .ok_or_else(|| Error::UnknownInputType(options.input_type.clone()))?;
+            Some(converter)
+        } else {
+            None
+        };
+
+        Ok(Prettifier { options, converter })
+    }
+
+    pub fn prettify(&self, input: &[u8]) -> Result<String, Error> {
+        let options = self.options;
+        if let Some(converter) = &self.converter {
+            let candidates = vec![(converter.as_ref(), 100)];
+            try_converters(candidates, false, options, |converter| {
+                let output = converter.prettify(input, options)?;
+                Ok(apply_final_newline(converter, output, input, options))
+            })
+            .map(|(output, _)| output)
+        } else if !options.pipeline.is_empty() {
+            pipeline::prettify(input, options)
+        } else if options.recursion_depth > 0 && !options.reverse {
+            recursive::prettify(input, options)
+        } else {
+            convert(input, options).map(|(output, _)| output)
+        }
+    }
+}
+
+/// Like `prettify`, but returns bytes so binary results, like decoded Base64 images, are kept as is.
+pub fn prettify_bytes(input: &[u8], options: &Options) -> Result<Vec<u8>, Error> {
+    validate_options(options)?;
+    if !options.pipeline.is_empty() {
+        pipeline::prettify_bytes(input, options)
+    } else if options.recursion_depth > 0 && !options.reverse {
+        recursive::prettify(input, options).map(String::into_bytes)
+    } else {
+        convert_with(input, options, |converter| {
+            let output = converter.prettify_bytes(input, options)?;
+            Ok(match String::from_utf8(output) {
+                Ok(text) => apply_final_newline(converter, text, input, options).into_bytes(),
+                Err(e) => e.into_bytes(),
+            })
+        })
+        .map(|(output, _)| output)
+    }
+}
+
+/// Checks the converter specific options against the schema of every converter that reads them.
+/// Options set for a single converter must also be ones that converter declares.
+pub fn validate_options(options: &Options) -> Result<(), Error> {
+    let converters = converters::get_converters();
+    for name in options.per_converter.keys() {
+        if !converters
+            .iter()
+            .any(|c| c.name().eq_ignore_ascii_case(name))
+        {
+            return Err(Error::UnknownInputType(name.clone()));
+        }
+    }
+
+    for converter in converters {
+        let schemas = converter.options();
+        if let Some(own) = options.per_converter.get(&converter.name().to_lowercase()) {
+            let mut names: Vec<&String> = own.keys().collect();
+            names.sort();
+            for name in names {
+                if !schemas.iter().any(|schema| schema.name == name) {
+                    return Err(Error::UnknownOption {
+                        converter: String::from(converter.name()),
+                        option: name.clone(),
+                        known: schemas.iter().map(|s| String::from(s.name)).collect(),
+                    });
+                }
+            }
+        }
+
+        let converter_options = options.converter_options(converter.name());
+        for schema in schemas {
+            let value = match converter_options.get(schema.name) {
+                Some(value) => value,
+                None => continue,
             };
-            match converter.prettify(input, options) {
-                Ok(output) => {
-                    if options.verbose {
-                        println!("Conversion successful!");
-                    }
-                    return Ok(output);
+            schema
+                .validate(value)
+                .map_err(|expected| Error::InvalidOption {
+                    option: String::from(schema.name),
+                    value: value.clone(),
+                    expected,
+                })?;
+        }
+    }
+
+    Ok(())
+}
+
+pub fn detect(input: &[u8]) -> Vec<(String, u8)> {
+    rank_converters(input)
+        .into_iter()
+        .map(|(converter, score)| (String::from(converter.name()), score))
+        .collect()
+}
+
+fn rank_converters(input: &[u8]) -> Vec<(Box<dyn Converter>, u8)> {
+    let mut ranked: Vec<(Box<dyn Converter>, u8)> = converters::get_converters()
+        .into_iter()
+        .map(|converter| {
+            let score = converter.detect(input);
+            (converter, score)
+        })
+        .collect();
+    ranked.sort_by_key(|(_, score)| Reverse(*score));
+
+    ranked
+}
+
+fn convert(input: &[u8], options: &Options) -> Result<(String, String), Error> {
+    convert_with(input, options, |converter| {
+        let output = converter.prettify(input, options)?;
+        Ok(apply_final_newline(converter, output, input, options))
+    })
+}
+
+/// Converters write the line endings chosen with `end_of_line` themselves, and the final one is
+/// added or removed the same way for every converter that declares the options.
+fn apply_final_newline(
+    converter: &dyn Converter,
+    output: String,
+    input: &[u8],
+    options: &Options,
+) -> String {
+    let declares = converter
+        .options()
+        .iter()
+        .any(|schema| schema.name == converter_utils::END_OF_LINE_OPTION_NAME);
+    if declares {
+        let input_has_final_newline =
+            text::decode(input).is_some_and(|input| input.ends_with(['\n', '\r']));
+        converter_utils::apply_final_newline(
+            output,
+            &options.converter_options(converter.name()),
+            input_has_final_newline,
+        )
+    } else {
+        output
+    }
+}
+
+fn convert_with<T>(
+    input: &[u8],
+    options: &Options,
+    run: impl Fn(&dyn Converter) -> Result<T, Error>,
+) -> Result<(T, String), Error> {
+    let detecting = options.input_type.eq_ignore_ascii_case("all");
+    let candidates = if detecting {
+        rank_converters(input)
+    } else {
+        converters::get_converters()
+            .into_iter()
+            .filter(|c| options.input_type.eq_ignore_ascii_case(c.name()))
+            .map(|converter| (converter, 100))
+            .collect()
+    };
+
+    let candidates = candidates
+        .iter()
+        .map(|(converter, score)| (converter.as_ref(), *score))
+        .collect();
+    try_converters(candidates, detecting, options, run)
+}
+
+/// Runs the candidates in order until one of them converts the input.
+fn try_converters<T>(
+    candidates: Vec<(&dyn Converter, u8)>,
+    detecting: bool,
+    options: &Options,
+    run: impl Fn(&dyn Converter) -> Result<T, Error>,
+) -> Result<(T, String), Error> {
+    let mut errors = Vec::new();
+    for (converter, score) in candidates {
+        if options.verbose {
+            if detecting {
+                println!(
+                    "Trying to convert as {} (confidence {})",
+                    converter.name(),
+                    score
+                );
+            } else {
+                println!("Trying to convert as {}", converter.name());
+            }
+        };
+        match run(converter) {
+            Ok(_) if score == 0 => {
+                let e = Error::cannot_convert(
+                    converter.name(),
+                    "input was accepted but does not look like it is in this format",
+                );
+                if options.verbose {
+                    println!("Conversion skipped: {}", e);
                 }
-                Err(_) => {
-                    if options.verbose {
-                        println!("Conversion failed.");
-                    }
+                errors.push(e);
+            }
+            Ok(output) => {
+                if options.verbose {
+                    println!("Conversion successful!");
+                }
+                return Ok((output, String::from(converter.name())));
+            }
+            Err(e) => {
+                if options.verbose {
+                    println!("Conversion failed: {}", e);
                 }
+                errors.push(e);
             }
         }
     }
 
-    Err(String::from(input))
+    if errors.is_empty() {
+        Err(Error::UnknownInputType(options.input_type.clone()))
+    } else {
+        Err(Error::NoConverterMatched(errors))
+    }
 }
 
-fn should_try_converter(input_type: &str, converter_name: &str) -> bool {
-    input_type.eq_ignore_ascii_case("all") || input_type.eq_ignore_ascii_case(converter_name)
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            detect("{\"a\": [1, 2]}".as_bytes()),
+            vec![
+                (String::from("JSON"), 100),
+                (String::from("YAML"), 40),
+                (String::from("XML"), 0),
+                (String::from("CSV"), 0),
+                (String::from("Base64"), 0),
+            ]
+        );
+        assert_eq!(
+            detect("aGVsbG8gd29ybGQh".as_bytes())[0],
+            (String::from("Base64"), 60)
+        );
+    }
+
+    #[test]
+    fn test_prettify_picks_most_confident() {
+        let mut options = Options::default();
+        options
+            .converter_specific
+            .insert(String::from("color"), String::from("false"));
+
+        assert_eq!(
+            convert("a: 1\nb: [x, y]".as_bytes(), &options).map(|(_, name)| name),
+            Ok(String::from("YAML"))
+        );
+        assert_eq!(
+            convert("aGVsbG8gd29ybGQh".as_bytes(), &options),
+            Ok((String::from("hello world!"), String::from("Base64")))
+        );
+        assert!(matches!(
+            convert("AAAA".as_bytes(), &options),
+            Err(Error::NoConverterMatched(_))
+        ));
+        options.input_type = String::from("base64");
+        assert_eq!(
+            convert("AAAA".as_bytes(), &options),
+            Ok((String::from("\0\0\0"), String::from("Base64")))
+        );
+    }
+
+    #[test]
+    fn test_prettifier() {
+        let mut options = Options {
+            input_type: String::from("csv"),
+            ..Options::default()
+        };
+        let prettifier = Prettifier::new(&options).unwrap();
+        assert_eq!(
+            prettifier.prettify("a,bb".as_bytes()),
+            Ok(String::from("a, bb"))
+        );
+        assert!(matches!(
+            prettifier.prettify("\"a".as_bytes()),
+            Err(Error::NoConverterMatched(_))
+        ));
+
+        options.input_type = String::from("toml");
+        assert!(matches!(
+            Prettifier::new(&options),
+            Err(Error::UnknownInputType(input_type)) if input_type == "toml"
+        ));
+    }
+
+    #[test]
+    fn test_validate_options() {
+        let mut options = Options::default();
+        options
+            .converter_specific
+            .insert(String::from("indent"), String::from("2"));
+        options
+            .converter_specific
+            .insert(String::from("alphabet"), String::from("url"));
+        assert_eq!(validate_options(&options), Ok(()));
+
+        options
+            .converter_specific
+            .insert(String::from("indent"), String::from("300"));
+        assert_eq!(
+            prettify("{}".as_bytes(), &options),
+            Err(Error::InvalidOption {
+                option: String::from("indent"),
+                value: String::from("300"),
+                expected: String::from("a number from 0 to 16"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_validate_per_converter_options() {
+        let mut options = Options::default();
+        let mut csv = HashMap::new();
+        csv.insert(String::from("separator"), String::from(";"));
+        options.per_converter.insert(String::from("csv"), csv);
+        assert_eq!(validate_options(&options), Ok(()));
+
+        options
+            .per_converter
+            .get_mut("csv")
+            .unwrap()
+            .insert(String::from("spacing"), String::from("lots"));
+        assert!(matches!(
+            validate_options(&options),
+            Err(Error::InvalidOption { .. })
+        ));
+
+        options
+            .per_converter
+            .get_mut("csv")
+            .unwrap()
+            .insert(String::from("indent"), String::from("2"));
+        assert_eq!(
+            validate_options(&options),
+            Err(Error::UnknownOption {
+                converter: String::from("CSV"),
+                option: String::from("indent"),
+                known: vec![
+                    String::from("separator"),
+                    String::from("spacing"),
+                    String::from("end_of_line"),
+                    String::from("insert_final_newline"),
+                ],
+            })
+        );
+
+        options.per_converter.clear();
+        options
+            .per_converter
+            .insert(String::from("toml"), HashMap::new());
+        assert_eq!(
+            validate_options(&options),
+            Err(Error::UnknownInputType(String::from("toml")))
+        );
+    }
+
+    #[test]
+    fn test_newline_options() {
+        let mut options = Options {
+            input_type: String::from("json"),
+            ..Options::default()
+        };
+        options
+            .converter_specific
+            .insert(String::from("color"), String::from("false"));
+        options
+            .converter_specific
+            .insert(String::from("indent_style"), String::from("tab"));
+        options
+            .converter_specific
+            .insert(String::from("end_of_line"), String::from("crlf"));
+        options
+            .converter_specific
+            .insert(String::from("insert_final_newline"), String::from("true"));
+
+        assert_eq!(
+            prettify("{\"a\":1}".as_bytes(), &options),
+            Ok(String::from("{\r\n\t\"a\": 1\r\n}\r\n"))
+        );
+
+        options.input_type = String::from("csv");
+        options
+            .converter_specific
+            .insert(String::from("insert_final_newline"), String::from("false"));
+        assert_eq!(
+            prettify_bytes("a,b\n1,2\n".as_bytes(), &options),
+            Ok("a, b\r\n1, 2".as_bytes().to_vec())
+        );
+        assert_eq!(
+            prettify("a,b\n\"x\ny\",2\n".as_bytes(), &options),
+            Ok(String::from("a,     b\r\n\"x\ny\", 2"))
+        );
+
+        options.input_type = String::from("xml");
+        assert_eq!(
+            prettify("<a><b>x\ny</b><![CDATA[\n]]></a>".as_bytes(), &options),
+            Ok(String::from(
+                "<a>\r\n\t<b>x\ny</b>\r\n\t<![CDATA[\n]]>\r\n</a>"
+            ))
+        );
+
+        options.input_type = String::from("base64");
+        assert_eq!(
+            prettify("YQpi".as_bytes(), &options),
+            Ok(String::from("a\nb"))
+        );
+    }
+
+    #[test]
+    fn test_keeps_final_newline_of_input() {
+        let mut options = Options {
+            input_type: String::from("yaml"),
+            ..Options::default()
+        };
+        options
+            .converter_specific
+            .insert(String::from("color"), String::from("false"));
+
+        assert_eq!(
+            prettify_bytes("a:   1\n".as_bytes(), &options),
+            Ok("a: 1\n".as_bytes().to_vec())
+        );
+        assert_eq!(
+            prettify_bytes("a:   1".as_bytes(), &options),
+            Ok("a: 1".as_bytes().to_vec())
+        );
+
+        options
+            .converter_specific
+            .insert(String::from("insert_final_newline"), String::from("false"));
+        assert_eq!(
+            prettify_bytes("a:   1\n".as_bytes(), &options),
+            Ok("a: 1".as_bytes().to_vec())
+        );
+    }
+
+    #[test]
+    fn test_option_defaults_are_valid() {
+        for converter in converters::get_converters() {
+            for schema in converter.options() {
+                if let Some(default) = schema.default {
+                    assert_eq!(schema.validate(default), Ok(()), "{}", schema.name);
+                }
+            }
+        }
+    }
 }
@@ -1,82 +1,683 @@
-use prettify;
-use prettify::options::Options;
+use prettify::atomic_file::{self, AtomicFile};
+use prettify::color::ColorChoice;
+use prettify::config::{self, Config};
+use prettify::converters;
+use prettify::converters::json_converter::{self, JsonConverter};
+use prettify::diff;
+use prettify::error::{Error, Location};
+use prettify::options::{OptionType, Options};
+use prettify::parallel;
+use prettify::theme::{self, Theme};
+use prettify::Prettifier;
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fs;
-use std::io::{self, BufRead};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 use std::process;
+use std::thread;
 extern crate clap;
 use clap::{crate_authors, crate_description, crate_name, crate_version, App, Arg, ArgMatches};
+use rayon::ThreadPool;
+
+/// How many bytes at the start of a large file are read to tell whether it is JSON to stream.
+const STREAM_SNIFF_SIZE: u64 = 4096;
 
 fn main() {
     let matches = get_matches();
 
+    if matches.occurrences_of("list options") > 0 {
+        print_converter_options();
+        return;
+    }
+
+    let mut options = build_options(&matches);
+    if let Some(file) = matches.value_of("file").filter(|_| !is_batch(&matches)) {
+        if let Err(e) = apply_editorconfig(Path::new(file), &mut options, &matches) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+
+    if matches.occurrences_of("print config") > 0 {
+        print!("{}", config::to_toml(&options));
+        return;
+    }
+
+    if is_batch(&matches) {
+        format_files(&matches, &options);
+        return;
+    }
+
+    if matches.occurrences_of("detect") > 0 {
+        print_detection(&build_input(&matches));
+        return;
+    }
+
+    if should_check(&matches) {
+        let input = build_input(&matches);
+        let name = matches.value_of("file").unwrap_or("<input>");
+        let show_diff = matches.occurrences_of("diff") > 0;
+        let mut report = Report::default();
+        let result = check_formatting(&input, &options, name, show_diff, &mut report);
+        report.print();
+        if result != FileResult::Unchanged {
+            process::exit(1);
+        }
+        return;
+    }
+
+    if should_stream(&matches, &options) {
+        stream_json(&matches, &options);
+        return;
+    }
+
     let input = build_input(&matches);
-    if input.is_empty() {
-        eprintln!("No input given.");
-        eprintln!("Please provide either a file name, the string in an arguement, or stdin.");
-        process::exit(1);
+    if matches.occurrences_of("html") > 0 {
+        write_html(&matches, &input, &options);
+        return;
     }
 
-    let options = build_options(&matches);
+    if let Some(output) = get_output_path(&matches) {
+        make_backup(&matches);
+        write_output(&input, &options, output);
+        return;
+    }
 
-    let mut inputs: Vec<&str> = Vec::new();
+    let text;
+    let mut inputs: Vec<&[u8]> = Vec::new();
     if should_split_lines(&matches) {
-        inputs = input.lines().collect();
+        text = input_text(&input);
+        inputs = text.lines().map(str::as_bytes).collect();
     } else {
         inputs.push(&input);
     }
 
+    let prettifier = match Prettifier::new(&options) {
+        Ok(prettifier) => prettifier,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    let pool = build_thread_pool(&matches);
     let mut any_errors = false;
-    for i in inputs {
-        let result = prettify::prettify(i, &options);
-        print_results(&result);
-        if result.is_err() {
-            any_errors = true;
+    parallel::for_each_ordered(
+        &pool,
+        &inputs,
+        |i| prettifier.prettify(i),
+        |i, result| {
+            print_results(&result, i);
+            if result.is_err() {
+                any_errors = true;
+            }
+        },
+    );
+
+    if any_errors {
+        process::exit(1);
+    }
+}
+
+/// Output of a single input, gathered so inputs prettified in parallel can be printed in order.
+#[derive(Default)]
+struct Report {
+    output: String,
+    errors: String,
+}
+
+impl Report {
+    fn print(&self) {
+        print!("{}", self.output);
+        eprint!("{}", self.errors);
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum FileResult {
+    Unchanged,
+    Changed,
+    Failed,
+}
+
+/// Whether more than a single file was asked for, in which case each one is formatted on its own
+/// and a summary is printed at the end.
+fn is_batch(matches: &ArgMatches) -> bool {
+    match matches.values_of("file") {
+        Some(files) => {
+            let files: Vec<&str> = files.collect();
+            files.len() > 1
+                || files
+                    .iter()
+                    .any(|f| prettify::files::is_glob(f) || Path::new(f).is_dir())
         }
+        None => false,
     }
+}
 
-    if any_errors {
+fn format_files(matches: &ArgMatches, options: &Options) {
+    if should_split_lines(matches)
+        || matches.occurrences_of("output") > 0
+        || matches.occurrences_of("html") > 0
+    {
+        eprintln!("--lines, --output and --html can only be used with a single file.");
+        process::exit(1);
+    }
+
+    let paths: Vec<&str> = matches.values_of("file").unwrap().collect();
+    let excludes: Vec<&str> = matches
+        .values_of("exclude")
+        .map(|e| e.collect())
+        .unwrap_or_default();
+    let files = match prettify::files::find_files(&paths, &excludes) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+    if files.is_empty() {
+        eprintln!("No files found to prettify.");
         process::exit(1);
     }
+
+    let pool = build_thread_pool(matches);
+    let mut results = Vec::new();
+    parallel::for_each_ordered(
+        &pool,
+        &files,
+        |file| format_file(file, options, matches),
+        |_, (result, report)| {
+            if !results.is_empty() && !should_check(matches) && get_output_path(matches).is_none() {
+                println!();
+            }
+            report.print();
+            results.push(result);
+        },
+    );
+
+    print_summary(matches, &files, &results);
+    if results.contains(&FileResult::Failed)
+        || (should_check(matches) && results.contains(&FileResult::Changed))
+    {
+        process::exit(1);
+    }
+}
+
+fn format_file(file: &Path, options: &Options, matches: &ArgMatches) -> (FileResult, Report) {
+    let mut report = Report::default();
+    let result = format_file_into(file, options, matches, &mut report);
+    (result, report)
+}
+
+fn format_file_into(
+    file: &Path,
+    options: &Options,
+    matches: &ArgMatches,
+    report: &mut Report,
+) -> FileResult {
+    let name = file.display().to_string();
+    let input = match fs::read(file) {
+        Ok(input) => input,
+        Err(e) => {
+            report
+                .errors
+                .push_str(&format!("Could not read '{}': {}\n", name, e));
+            return FileResult::Failed;
+        }
+    };
+
+    let mut options = options.clone();
+    if options.pipeline.is_empty() && options.input_type.eq_ignore_ascii_case("all") {
+        if let Some(input_type) = prettify::files::input_type_for_path(file) {
+            options.input_type = String::from(input_type);
+        }
+    }
+
+    if let Err(e) = apply_editorconfig(file, &mut options, matches) {
+        report.errors.push_str(&format!("{}\n", e));
+        return FileResult::Failed;
+    }
+
+    if should_check(matches) {
+        let show_diff = matches.occurrences_of("diff") > 0;
+        return check_formatting(&input, &options, &name, show_diff, report);
+    }
+
+    if matches.occurrences_of("in place") > 0 {
+        return match prettify::prettify_bytes(&input, &options) {
+            Ok(output) if is_formatted(&input, &output) => FileResult::Unchanged,
+            Ok(output) => {
+                make_file_backup(&name, matches.value_of("in place"));
+                match atomic_file::write(file, &output) {
+                    Ok(()) => FileResult::Changed,
+                    Err(e) => {
+                        report
+                            .errors
+                            .push_str(&format!("Could not write to '{}': {}\n", name, e));
+                        FileResult::Failed
+                    }
+                }
+            }
+            Err(e) => {
+                report.errors.push_str(&format!("{}:\n", name));
+                report.errors.push_str(&error_report(&e, &input));
+                FileResult::Failed
+            }
+        };
+    }
+
+    report.output.push_str(&format!("==> {} <==\n", name));
+    match prettify::prettify(&input, &options) {
+        Ok(s) => {
+            report.output.push_str(&format!("{}{}", s, line_break(&s)));
+            FileResult::Changed
+        }
+        Err(e) => {
+            report.errors.push_str(&format!("{}:\n", name));
+            report.errors.push_str(&error_report(&e, &input));
+            FileResult::Failed
+        }
+    }
+}
+
+fn print_summary(matches: &ArgMatches, files: &[PathBuf], results: &[FileResult]) {
+    let count = |result: FileResult| results.iter().filter(|r| **r == result).count();
+    let (unchanged, changed, failed) = (
+        count(FileResult::Unchanged),
+        count(FileResult::Changed),
+        count(FileResult::Failed),
+    );
+
+    eprintln!();
+    if should_check(matches) {
+        eprintln!(
+            "Checked {} files: {} formatted, {} not formatted, {} failed.",
+            files.len(),
+            unchanged,
+            changed,
+            failed
+        );
+    } else if matches.occurrences_of("in place") > 0 {
+        eprintln!(
+            "Formatted {} files: {} changed, {} unchanged, {} failed.",
+            files.len(),
+            changed,
+            unchanged,
+            failed
+        );
+    } else {
+        eprintln!(
+            "Prettified {} files: {} succeeded, {} failed.",
+            files.len(),
+            changed,
+            failed
+        );
+    }
+    for (file, result) in files.iter().zip(results) {
+        if *result == FileResult::Failed {
+            eprintln!("Failed: {}", file.display());
+        }
+    }
+}
+
+fn should_stream(matches: &ArgMatches, options: &Options) -> bool {
+    let input_type = &options.input_type;
+    let is_json = input_type.eq_ignore_ascii_case("json") || input_type.eq_ignore_ascii_case("all");
+    if matches.occurrences_of("stream") > 0 {
+        if !is_json {
+            eprintln!("Only JSON can be streamed, use '--type json' with '--stream'.");
+            process::exit(1);
+        }
+        return true;
+    }
+
+    let file = match matches.value_of("file") {
+        Some(file) => file,
+        None => return false,
+    };
+    if should_split_lines(matches)
+        || matches.occurrences_of("recursive") > 0
+        || matches.occurrences_of("html") > 0
+    {
+        return false;
+    }
+
+    let size = fs::metadata(file).map(|m| m.len()).unwrap_or(0);
+    let mut start = Vec::new();
+    if let Ok(f) = fs::File::open(file) {
+        let _ = f.take(STREAM_SNIFF_SIZE).read_to_end(&mut start);
+    }
+    json_converter::should_stream(input_type, size, &start)
+}
+
+fn stream_json(matches: &ArgMatches, options: &Options) {
+    let output = get_output_path(matches);
+    if output.is_some() {
+        make_backup(matches);
+    }
+    let result = if let Some(f) = matches.value_of("file") {
+        match fs::File::open(f) {
+            Ok(file) => write_stream(
+                BufReader::new(file),
+                &keep_final_newline(Path::new(f), options),
+                output,
+            ),
+            Err(e) => {
+                eprintln!("Could not read '{}': {}", f, e);
+                process::exit(1);
+            }
+        }
+    } else if let Some(input) = matches.value_of("INPUT") {
+        write_stream(input.as_bytes(), options, output)
+    } else {
+        write_stream(io::stdin().lock(), options, output)
+    };
+
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+}
+
+/// The streamed output of a file ends with a line ending when the file does, unless
+/// `insert_final_newline` says otherwise.
+fn keep_final_newline<'a>(file: &Path, options: &'a Options) -> Cow<'a, Options> {
+    let is_set = options
+        .converter_options("JSON")
+        .contains_key("insert_final_newline");
+    let mut last_byte = [0];
+    let ends_with_newline = fs::File::open(file)
+        .and_then(|mut f| {
+            f.seek(SeekFrom::End(-1))?;
+            f.read_exact(&mut last_byte)
+        })
+        .is_ok()
+        && (last_byte[0] == b'\n' || last_byte[0] == b'\r');
+    if is_set || !ends_with_newline {
+        return Cow::Borrowed(options);
+    }
+    let mut options = options.clone();
+    options
+        .per_converter
+        .entry(String::from("json"))
+        .or_default()
+        .insert(String::from("insert_final_newline"), String::from("true"));
+    Cow::Owned(options)
+}
+
+fn write_stream<R: BufRead>(
+    reader: R,
+    options: &Options,
+    output: Option<&str>,
+) -> Result<(), Error> {
+    let converter = JsonConverter::new();
+    match output {
+        Some(path) => {
+            let mut file = match AtomicFile::create(Path::new(path)) {
+                Ok(file) => file,
+                Err(e) => {
+                    eprintln!("Could not write to '{}': {}", path, e);
+                    process::exit(1);
+                }
+            };
+            converter.prettify_stream(reader, BufWriter::new(&mut file), options)?;
+            file.commit()
+                .map_err(|e| Error::cannot_convert("JSON", &e.to_string()))
+        }
+        None => {
+            let stdout = io::stdout();
+            let mut writer = BufWriter::new(stdout.lock());
+            converter.prettify_stream(reader, &mut writer, options)?;
+            if !converter.ends_with_newline(options) {
+                writer
+                    .write_all(converter.newline(options).as_bytes())
+                    .map_err(|e| Error::cannot_convert("JSON", &e.to_string()))?;
+            }
+            writer
+                .flush()
+                .map_err(|e| Error::cannot_convert("JSON", &e.to_string()))
+        }
+    }
+}
+
+fn get_output_path<'a>(matches: &'a ArgMatches) -> Option<&'a str> {
+    if matches.occurrences_of("in place") > 0 {
+        matches.value_of("file")
+    } else {
+        matches.value_of("output")
+    }
+}
+
+fn make_backup(matches: &ArgMatches) {
+    if let Some(file) = matches.value_of("file") {
+        make_file_backup(file, matches.value_of("in place"));
+    }
+}
+
+fn make_file_backup(file: &str, suffix: Option<&str>) {
+    if let Some(suffix) = suffix {
+        let backup = format!("{}{}", file, suffix);
+        if let Err(e) = fs::copy(file, &backup) {
+            eprintln!("Could not back up '{}' to '{}': {}", file, backup, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn should_check(matches: &ArgMatches) -> bool {
+    matches.occurrences_of("check") > 0
+}
+
+/// Prints the name, or a diff, of input that is not formatted yet.
+fn check_formatting(
+    input: &[u8],
+    options: &Options,
+    name: &str,
+    show_diff: bool,
+    report: &mut Report,
+) -> FileResult {
+    match prettify::prettify_bytes(input, options) {
+        Ok(output) => {
+            if is_formatted(input, &output) {
+                return FileResult::Unchanged;
+            }
+
+            if show_diff {
+                let (input, output) = (input_text(input), String::from_utf8_lossy(&output));
+                let diff =
+                    diff::unified_diff(&input, &output, name, &format!("{} (formatted)", name));
+                if diff.is_empty() {
+                    report.output.push_str(&format!(
+                        "{}: {}\n",
+                        name,
+                        unseen_change(&input, &output)
+                    ));
+                } else {
+                    report.output.push_str(&diff);
+                }
+            } else {
+                report.output.push_str(&format!("{}\n", name));
+            }
+            FileResult::Changed
+        }
+        Err(e) => {
+            report.errors.push_str(&format!("{}:\n", name));
+            report.errors.push_str(&error_report(&e, input));
+            FileResult::Failed
+        }
+    }
+}
+
+/// Why formatting changes input when a diff of its lines shows no change.
+fn unseen_change(input: &str, output: &str) -> &'static str {
+    if input == output {
+        "encoding differs"
+    } else if input.contains("\r\n") != output.contains("\r\n") {
+        "line endings differ"
+    } else {
+        "line breaks at the end differ"
+    }
+}
+
+fn is_formatted(input: &[u8], output: &[u8]) -> bool {
+    input == output
+        || input.strip_suffix(b"\n") == Some(output)
+        || input.strip_suffix(b"\r\n") == Some(output)
 }
 
 fn should_split_lines(matches: &ArgMatches) -> bool {
     matches.occurrences_of("lines") > 0
 }
 
-fn print_results(result: &Result<String, String>) {
+fn input_text(input: &[u8]) -> Cow<'_, str> {
+    match prettify::text::decode(input) {
+        Some(text) => text,
+        None => String::from_utf8_lossy(input),
+    }
+}
+
+fn print_results(result: &Result<String, Error>, input: &[u8]) {
+    match result {
+        Ok(s) => print!("{}{}", s, line_break(s)),
+        Err(e) => print_error(e, input),
+    }
+}
+
+/// The line break to print after a result, unless it already ends with one.
+fn line_break(output: &str) -> &'static str {
+    if output.ends_with(['\n', '\r']) {
+        ""
+    } else {
+        "\n"
+    }
+}
+
+fn write_output(input: &[u8], options: &Options, output: &str) {
+    let result = prettify::prettify_bytes(input, options);
     match result {
-        Ok(s) => println!("{}", s),
-        Err(s) => {
-            eprintln!("Input was not in any recognized format.");
-            eprintln!("{}", s);
+        Ok(bytes) => {
+            if let Err(e) = atomic_file::write(Path::new(output), &bytes) {
+                eprintln!("Could not write to '{}': {}", output, e);
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            print_error(&e, input);
+            process::exit(1);
         }
     }
 }
 
-fn build_input(matches: &ArgMatches) -> String {
-    match get_input_from_file(matches.value_of("file")) {
-        Ok(s) => return s,
-        Err(_) => (),
+fn write_html(matches: &ArgMatches, input: &[u8], options: &Options) {
+    let standalone = matches.value_of("html") != Some("fragment");
+    let html = match prettify::html::prettify_html(input, options, standalone) {
+        Ok(html) => html,
+        Err(e) => {
+            print_error(&e, input);
+            process::exit(1);
+        }
+    };
+    match matches.value_of("output") {
+        Some(output) => {
+            if let Err(e) = atomic_file::write(Path::new(output), html.as_bytes()) {
+                eprintln!("Could not write to '{}': {}", output, e);
+                process::exit(1);
+            }
+        }
+        None => println!("{}", html),
     }
+}
 
-    match get_input_from_arg(matches.value_of("INPUT")) {
-        Ok(s) => return s,
-        Err(_) => (),
+fn print_detection(input: &[u8]) {
+    let candidates: Vec<(String, u8)> = prettify::detect(input)
+        .into_iter()
+        .filter(|(_, score)| *score > 0)
+        .collect();
+
+    if candidates.is_empty() {
+        eprintln!("Input was not in any recognized format.");
+        process::exit(1);
     }
 
-    match get_input_from_stdin() {
-        Ok(s) => return s,
-        Err(_) => (),
+    for (name, score) in candidates {
+        println!("{}: {}", name, score);
     }
+}
+
+fn print_converter_options() {
+    for (i, converter) in converters::get_converters().iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+        println!("{}:", converter.name());
+        for schema in converter.options() {
+            let default = match schema.default {
+                Some(default) => format!(", default {}", default),
+                None => String::new(),
+            };
+            println!("    {} ({}{})", schema.name, schema.expected(), default);
+            println!("        {}", schema.help);
+        }
+    }
+}
 
-    String::new()
+fn print_error(error: &Error, input: &[u8]) {
+    eprint!("{}", error_report(error, input));
 }
 
-fn get_input_from_file(file: Option<&str>) -> Result<String, String> {
+fn error_report(error: &Error, input: &[u8]) -> String {
+    let mut report = format!("{}\n", error);
+    if let Error::NoConverterMatched(errors) = error {
+        for e in errors {
+            report.push('\n');
+            report.push_str(&error_report(e, input));
+        }
+    } else if let Error::StageFailed { input, cause, .. } = error {
+        report.push_str(&error_report(cause, input));
+    } else if let Some(location) = error.location() {
+        report.push_str(&snippet(&input_text(input), &location));
+    }
+
+    report
+}
+
+fn snippet(input: &str, location: &Location) -> String {
+    let line = input.lines().nth(location.line - 1).unwrap_or("");
+    let gutter = location.line.to_string();
+    let padding: String = line
+        .chars()
+        .take(location.column - 1)
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+
+    format!(
+        "{} | {}\n{} | {}^\n",
+        gutter,
+        line,
+        " ".repeat(gutter.len()),
+        padding
+    )
+}
+
+/// Reads the input from the file, the argument or stdin, and exits if there is none.
+fn build_input(matches: &ArgMatches) -> Vec<u8> {
+    let input = get_input_from_file(matches.value_of("file"))
+        .or_else(|_| get_input_from_arg(matches.value_of("INPUT")))
+        .or_else(|_| get_input_from_stdin())
+        .unwrap_or_default();
+    if input.is_empty() {
+        eprintln!("No input given.");
+        eprintln!("Please provide either a file name, the string in an arguement, or stdin.");
+        process::exit(1);
+    }
+    input
+}
+
+fn get_input_from_file(file: Option<&str>) -> Result<Vec<u8>, String> {
     match file {
         Some(f) => {
-            let contents = fs::read_to_string(f);
+            let contents = fs::read(f);
             match contents {
                 Ok(s) => Ok(s),
                 Err(_) => Err(String::new()),
@@ -86,22 +687,20 @@ fn get_input_from_file(file: Option<&str>) -> Result<String, String> {
     }
 }
 
-fn get_input_from_arg(arg: Option<&str>) -> Result<String, String> {
+fn get_input_from_arg(arg: Option<&str>) -> Result<Vec<u8>, String> {
     match arg {
-        Some(s) => Ok(String::from(s)),
+        Some(s) => Ok(s.as_bytes().to_vec()),
         None => Err(String::new()),
     }
 }
 
-fn get_input_from_stdin() -> Result<String, String> {
-    let mut input = String::new();
+fn get_input_from_stdin() -> Result<Vec<u8>, String> {
+    let mut input = Vec::new();
 
-    let stdin = io::stdin();
-    for line in stdin.lock().lines() {
-        let line = line.expect("Could not read line from standard in");
-        input.push_str(&line);
-        input.push('\n');
-    }
+    io::stdin()
+        .lock()
+        .read_to_end(&mut input)
+        .expect("Could not read from standard in");
 
     if !input.is_empty() {
         Ok(input)
@@ -110,40 +709,229 @@ fn get_input_from_stdin() -> Result<String, String> {
     }
 }
 
+/// Builds the options from the built in defaults, then the config files and then the flags given,
+/// each taking precedence over the ones before.
 fn build_options(matches: &ArgMatches) -> Options {
-    Options {
-        input_type: String::from(matches.value_of("type").unwrap()),
-        verbose: matches.occurrences_of("verbose") > 0,
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    };
+
+    let input_type = match (matches.occurrences_of("type"), &config.input_type) {
+        (0, Some(input_type)) => input_type.as_str(),
+        _ => matches.value_of("type").unwrap(),
+    };
+    let pipeline: Vec<String> = if input_type.contains(',') {
+        input_type
+            .split(',')
+            .map(|t| t.trim().to_string())
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    let mut options = Options {
+        input_type: String::from(input_type),
+        verbose: matches.occurrences_of("verbose") > 0 || config.verbose == Some(true),
         reverse: matches.occurrences_of("reverse") > 0,
-        converter_specific: get_converter_specific(matches),
+        recursion_depth: get_recursion_depth(matches),
+        pipeline,
+        converter_specific: get_converter_specific(),
+        per_converter: HashMap::new(),
+        theme: get_theme(matches, &config),
+    };
+    config.apply(&mut options);
+    apply_converter_flags(matches, &mut options);
+    for (converter, converter_options) in get_per_converter(matches) {
+        options
+            .per_converter
+            .entry(converter)
+            .or_default()
+            .extend(converter_options);
+    }
+    if let Err(e) = prettify::validate_options(&options) {
+        eprintln!("{}", e);
+        process::exit(1);
     }
+
+    options
 }
 
-fn get_converter_specific(matches: &ArgMatches) -> HashMap<String, String> {
+/// Sets the options the `.editorconfig` files for the file ask for, unless a flag sets them. They
+/// are shared by every converter, so options set for a single converter still take precedence.
+fn apply_editorconfig(
+    file: &Path,
+    options: &mut Options,
+    matches: &ArgMatches,
+) -> Result<(), String> {
+    if matches.occurrences_of("no editorconfig") > 0 {
+        return Ok(());
+    }
+
+    for (name, value) in prettify::editorconfig::options_for(file)? {
+        let flags: &[&str] = match name.as_str() {
+            "indent" => &["indent"],
+            "indent_style" => &["indent style"],
+            "end_of_line" => &["end of line"],
+            "insert_final_newline" => &["final newline", "no final newline"],
+            _ => &[],
+        };
+        if flags.iter().all(|flag| matches.occurrences_of(flag) == 0) {
+            options.converter_specific.insert(name, value);
+        }
+    }
+    Ok(())
+}
+
+fn get_theme(matches: &ArgMatches, config: &Config) -> Theme {
+    let name = matches
+        .value_of("theme")
+        .or(config.theme.as_deref())
+        .unwrap_or("default");
+    match config.resolve_theme(name) {
+        Ok(theme) => theme,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+/// The converter options used when neither a config file nor a flag sets them.
+fn get_converter_specific() -> HashMap<String, String> {
     let mut map = HashMap::new();
 
-    map.insert("indent".to_string(), get_indent(matches).to_string());
-    map.insert("color".to_string(), get_color(matches).to_string());
+    map.insert("color".to_string(), true.to_string());
+    map.insert("sort_keys".to_string(), false.to_string());
+    map.insert("padding".to_string(), true.to_string());
+
+    map
+}
+
+fn apply_converter_flags(matches: &ArgMatches, options: &mut Options) {
+    if matches.occurrences_of("indent") > 0 {
+        options.set_for_all("indent", matches.value_of("indent").unwrap());
+    }
+    if let Some(color) = get_color(matches) {
+        options.set_for_all("color", &color.to_string());
+    }
+    if get_output_path(matches).is_some() || should_check(matches) {
+        options.set_for_all("color", "false");
+    }
+    if get_sort_keys(matches) {
+        options.set_for_all("sort_keys", "true");
+    }
+    if !get_padding(matches) {
+        options.set_for_all("padding", "false");
+    }
+    if let Some(alphabet) = matches.value_of("alphabet") {
+        options.set_for_all("alphabet", alphabet);
+    }
+    if let Some(wrap) = matches.value_of("wrap") {
+        options.set_for_all("wrap", wrap);
+    }
+    if let Some(indent_style) = matches.value_of("indent style") {
+        options.set_for_all("indent_style", indent_style);
+    }
+    if let Some(end_of_line) = matches.value_of("end of line") {
+        options.set_for_all("end_of_line", end_of_line);
+    }
+    if matches.occurrences_of("final newline") > 0 {
+        options.set_for_all("insert_final_newline", "true");
+    }
+    if matches.occurrences_of("no final newline") > 0 {
+        options.set_for_all("insert_final_newline", "false");
+    }
+}
+
+/// Collects `--set converter.key=value` options into a map for each converter.
+fn get_per_converter(matches: &ArgMatches) -> HashMap<String, HashMap<String, String>> {
+    let mut map: HashMap<String, HashMap<String, String>> = HashMap::new();
+    for setting in matches.values_of("set").into_iter().flatten() {
+        let parsed = setting
+            .split_once('=')
+            .and_then(|(key, value)| key.split_once('.').map(|(c, o)| (c, o, value)));
+        match parsed {
+            Some((converter, option, value)) if !converter.is_empty() && !option.is_empty() => {
+                map.entry(converter.trim().to_lowercase())
+                    .or_default()
+                    .insert(option.trim().to_string(), value.to_string());
+            }
+            _ => {
+                eprintln!(
+                    "Option '{}' is not valid, it should look like 'json.indent=2'.",
+                    setting
+                );
+                process::exit(1);
+            }
+        }
+    }
 
     map
 }
 
-fn get_indent(matches: &ArgMatches) -> u8 {
-    let indent_str = matches.value_of("indent").unwrap();
-    match indent_str.parse::<u8>() {
-        Ok(i) => i,
+fn get_recursion_depth(matches: &ArgMatches) -> u8 {
+    if matches.occurrences_of("recursive") == 0 {
+        return 0;
+    }
+
+    let depth_str = matches.value_of("depth").unwrap();
+    match depth_str.parse::<u8>() {
+        Ok(d) => d,
         Err(_) => {
-            println!(
-                "Indent value '{}' is not valid. Defaulting to 4.",
-                indent_str
-            );
-            4
+            eprintln!("Depth value '{}' is not valid. Defaulting to 5.", depth_str);
+            5
         }
     }
 }
 
-fn get_color(matches: &ArgMatches) -> bool {
-    matches.occurrences_of("no color") == 0
+fn build_thread_pool(matches: &ArgMatches) -> ThreadPool {
+    let jobs = match matches.value_of("jobs") {
+        Some(jobs_str) => match jobs_str.parse::<usize>() {
+            Ok(j) if j > 0 => j,
+            _ => {
+                eprintln!(
+                    "Jobs value '{}' is not valid. Defaulting to the number of CPUs.",
+                    jobs_str
+                );
+                default_jobs()
+            }
+        },
+        None => default_jobs(),
+    };
+
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(jobs)
+        .build()
+        .expect("Could not start worker threads")
+}
+
+fn default_jobs() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Whether the flags and the terminal turn color on or off, or `None` to leave it to the config.
+fn get_color(matches: &ArgMatches) -> Option<bool> {
+    if matches.occurrences_of("no color") > 0 {
+        return Some(false);
+    }
+
+    let choice = ColorChoice::from_name(matches.value_of("color").unwrap()).unwrap();
+    match choice.should_color(io::stdout().is_terminal()) {
+        true if choice == ColorChoice::Auto => None,
+        color => Some(color),
+    }
+}
+
+fn get_sort_keys(matches: &ArgMatches) -> bool {
+    matches.occurrences_of("sort keys") > 0
+}
+
+fn get_padding(matches: &ArgMatches) -> bool {
+    matches.occurrences_of("no padding") == 0
 }
 
 fn get_matches() -> ArgMatches<'static> {
@@ -156,7 +944,7 @@ fn get_matches() -> ArgMatches<'static> {
                 .short("t")
                 .long("type")
                 .value_name("TYPE")
-                .help("Type of data in the input. The options are JSON, Base64, and all. All will try each type till one succeeds.")
+                .help("Type of data in the input. The options are JSON, XML, YAML, CSV, Base64, and all. All will try each type till one succeeds. Several types separated by commas, like 'base64,json', are run one after another on the output of the previous one.")
                 .takes_value(true)
                 .default_value("all"),
         )
@@ -165,15 +953,107 @@ fn get_matches() -> ArgMatches<'static> {
                 .short("i")
                 .long("indent")
                 .value_name("INDENT")
-                .help("Number of spaces to indent the output. (Only for formats: JSON)")
+                .help(&converter_option_help("indent", None)[..])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("indent style")
+                .long("indent-style")
+                .value_name("STYLE")
+                .help(&converter_option_help("indent_style", None)[..])
                 .takes_value(true)
-                .default_value("4"),
+                .possible_values(&["space", "tab"]),
+        )
+        .arg(
+            Arg::with_name("end of line")
+                .long("end-of-line")
+                .value_name("EOL")
+                .help(&converter_option_help("end_of_line", None)[..])
+                .takes_value(true)
+                .possible_values(&["lf", "crlf", "cr"]),
+        )
+        .arg(
+            Arg::with_name("final newline")
+                .long("final-newline")
+                .help(&converter_option_help("insert_final_newline", Some("Ends the output with a line ending."))[..]),
+        )
+        .arg(
+            Arg::with_name("no final newline")
+                .long("no-final-newline")
+                .help(&converter_option_help("insert_final_newline", Some("Makes sure the output does not end with a line ending."))[..])
+                .conflicts_with("final newline"),
+        )
+        .arg(
+            Arg::with_name("no editorconfig")
+                .long("no-editorconfig")
+                .help("Ignores .editorconfig files. Otherwise, when formatting files, the indent_style, indent_size, end_of_line and insert_final_newline properties from the .editorconfig files in their directories and the ones above are used, unless the flags above set them."),
+        )
+        .arg(
+            Arg::with_name("color")
+                .long("color")
+                .value_name("WHEN")
+                .help(&converter_option_help("color", Some("When to color the output. Auto colors when printing to a terminal, unless the NO_COLOR environment variable is set, and CLICOLOR_FORCE colors even when not."))[..])
+                .takes_value(true)
+                .possible_values(&["auto", "always", "never"])
+                .default_value("auto"),
+        )
+        .arg(
+            Arg::with_name("theme")
+                .long("theme")
+                .value_name("THEME")
+                .help(&format!("Color theme to use, one of {} or a theme defined under [themes.NAME] in a config file. (Only for formats: JSON, XML, YAML)", theme::BUILT_IN_THEMES.join(", "))[..])
+                .takes_value(true),
         )
         .arg(
             Arg::with_name("no color")
                 .short("c")
                 .long("no-color")
-                .help("Disables colored output. (Only for formats: JSON)"),
+                .help("Disables colored output, the same as --color=never."),
+        )
+        .arg(
+            Arg::with_name("sort keys")
+                .short("s")
+                .long("sort-keys")
+                .help(&converter_option_help("sort_keys", None)[..]),
+        )
+        .arg(
+            Arg::with_name("alphabet")
+                .long("alphabet")
+                .value_name("ALPHABET")
+                .help(&converter_option_help("alphabet", None)[..])
+                .takes_value(true)
+                .possible_values(&["standard", "url"]),
+        )
+        .arg(
+            Arg::with_name("no padding")
+                .long("no-padding")
+                .help(&converter_option_help("padding", Some("Leaves out the trailing '=' padding when encoding."))[..]),
+        )
+        .arg(
+            Arg::with_name("wrap")
+                .long("wrap")
+                .value_name("COLUMNS")
+                .help(&converter_option_help("wrap", None)[..])
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("set")
+                .long("set")
+                .value_name("CONVERTER.OPTION=VALUE")
+                .help("Sets an option for a single converter, like 'csv.separator=;' or 'json.indent=2'. Can be given more than once, and takes precedence over the flags above. The options each converter has are listed with --list-options.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("list options")
+                .long("list-options")
+                .help("Lists the options each converter has, which can be set with --set."),
+        )
+        .arg(
+            Arg::with_name("print config")
+                .long("print-config")
+                .help("Prints the settings that would be used, after reading the config files and the flags given, in the config file format. Settings are read from $XDG_CONFIG_HOME/prettify/config.toml and then the closest .prettifyrc in the current directory or its parents."),
         )
         .arg(
             Arg::with_name("verbose")
@@ -187,19 +1067,108 @@ fn get_matches() -> ArgMatches<'static> {
                 .long("reverse")
                 .help("Instead of prettifying, minimizes the input. For Base64, you need to specify '--type Base64' because otherwise it will skip trying that conversion since it is guaranteed to succeed."),
         )
+        .arg(
+            Arg::with_name("recursive")
+                .short("R")
+                .long("recursive")
+                .help("After converting, keeps decoding the output and any JSON string values that contain nested Base64 or JSON."),
+        )
+        .arg(
+            Arg::with_name("depth")
+                .long("depth")
+                .value_name("DEPTH")
+                .help("Maximum number of nested layers to decode when using --recursive.")
+                .takes_value(true)
+                .default_value("5"),
+        )
+        .arg(
+            Arg::with_name("detect")
+                .short("d")
+                .long("detect")
+                .help("Instead of converting, lists the formats the input looks like with a confidence score from 1 to 100, most likely first."),
+        )
+        .arg(
+            Arg::with_name("check")
+                .long("check")
+                .help("Instead of printing the result, checks that the input is already formatted. Prints the name of the file and exits with an error if it is not.")
+                .conflicts_with_all(&["output", "in place", "lines", "stream", "detect"]),
+        )
+        .arg(
+            Arg::with_name("diff")
+                .long("diff")
+                .help("With --check, prints a unified diff of the changes formatting would make instead of only the file name.")
+                .requires("check"),
+        )
+        .arg(
+            Arg::with_name("stream")
+                .long("stream")
+                .help("Formats JSON while reading it instead of loading it all into memory first. This is done on its own for files larger than 64 MiB. Keys can not be sorted while streaming.")
+                .conflicts_with_all(&["lines", "recursive", "detect"]),
+        )
         .arg(
             Arg::with_name("lines")
                 .short("l")
                 .long("lines")
                 .help("Treat each line as a seperate input."),
         )
+        .arg(
+            Arg::with_name("jobs")
+                .short("j")
+                .long("jobs")
+                .value_name("JOBS")
+                .help("Number of lines or files to prettify at the same time when using --lines or several files. Results are still printed in the order of the input. Defaults to the number of CPUs.")
+                .takes_value(true),
+        )
         .arg(
             Arg::with_name("file")
                 .short("f")
                 .long("file")
                 .value_name("FILE")
-                .help("File contents to prettify.")
-                .takes_value(true),
+                .help("File contents to prettify. Can be given more than once, and can be a directory, which is searched recursively, or a glob pattern like 'config/**/*.json'. With several files the converter is picked from each file's extension, falling back to trying each type.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("exclude")
+                .long("exclude")
+                .value_name("PATTERN")
+                .help("Skips files and directories matching this .gitignore style pattern when searching for files. Can be given more than once.")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .requires("file"),
+        )
+        .arg(
+            Arg::with_name("output")
+                .short("o")
+                .long("output")
+                .value_name("FILE")
+                .help("Writes the result to a file instead of printing it. Binary data, like a decoded Base64 image, is written as raw bytes.")
+                .takes_value(true)
+                .conflicts_with("lines"),
+        )
+        .arg(
+            Arg::with_name("in place")
+                .long("in-place")
+                .value_name("SUFFIX")
+                .help("Replaces the file given with --file with the result. If a suffix is given, like '--in-place=.bak', the original is kept with that suffix added to its name.")
+                .takes_value(true)
+                .min_values(0)
+                .require_equals(true)
+                .requires("file")
+                .conflicts_with_all(&["output", "lines"]),
+        )
+        .arg(
+            Arg::with_name("html")
+                .long("html")
+                .value_name("KIND")
+                .help("Writes the colored result as HTML instead of with terminal colors, styled with the theme. By default this is a standalone page, and '--html=fragment' gives only a <pre> element with a CSS class on each token, like 'prettify-key', to style yourself.")
+                .takes_value(true)
+                .min_values(0)
+                .require_equals(true)
+                .possible_values(&["page", "fragment"])
+                .conflicts_with_all(&["check", "in place", "lines", "stream", "detect"]),
         )
         .arg(
             Arg::with_name("INPUT")
@@ -209,10 +1178,31 @@ fn get_matches() -> ArgMatches<'static> {
         .get_matches()
 }
 
+/// Help for a flag that is passed on to converters, built from the schemas of the converters that
+/// read the option. Flags that turn an option off give their own description.
+fn converter_option_help(option: &str, flag_help: Option<&str>) -> String {
+    let mut formats = Vec::new();
+    let mut schema = None;
+    for converter in converters::get_converters() {
+        if let Some(s) = converter.options().into_iter().find(|s| s.name == option) {
+            formats.push(converter.name().to_string());
+            schema.get_or_insert(s);
+        }
+    }
+
+    let schema = schema.expect("Option is not read by any converter");
+    let help = match (flag_help, schema.option_type) {
+        (Some(help), _) => String::from(help),
+        (None, OptionType::Bool | OptionType::Choice(_)) => String::from(schema.help),
+        (None, _) => format!("{} Must be {}.", schema.help, schema.expected()),
+    };
+    format!("{} (Only for formats: {})", help, formats.join(", "))
+}
+
 fn description() -> String {
     let mut description = String::new();
     description.push_str(crate_description!());
-    description.push_str("\n");
+    description.push('\n');
     description.push_str("Some options are only for specific formats, which are specified below.");
 
     description
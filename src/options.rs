@@ -1,19 +1,197 @@
+use crate::theme::Theme;
 use std::collections::HashMap;
 
+#[derive(Clone)]
 pub struct Options {
     pub input_type: String,
     pub verbose: bool,
     pub reverse: bool,
+    pub recursion_depth: u8,
+    /// Types to convert with one after another, each stage getting the output of the previous one.
+    /// When empty, `input_type` is used as the only stage.
+    pub pipeline: Vec<String>,
     pub converter_specific: HashMap<String, String>,
+    /// Options for a single converter, keyed by the lowercase converter name. These take
+    /// precedence over `converter_specific`.
+    pub per_converter: HashMap<String, HashMap<String, String>>,
+    /// Styles for converters that color their output.
+    pub theme: Theme,
 }
 
-impl Options {
-    pub fn default() -> Options {
+impl Default for Options {
+    fn default() -> Options {
         Options {
             input_type: String::from("all"),
             verbose: false,
             reverse: false,
+            recursion_depth: 0,
+            pipeline: Vec::new(),
             converter_specific: HashMap::new(),
+            per_converter: HashMap::new(),
+            theme: Theme::default(),
+        }
+    }
+}
+
+impl Options {
+    /// The options the named converter reads, with its own options on top of the shared ones.
+    pub fn converter_options(&self, converter: &str) -> HashMap<String, String> {
+        let mut merged = self.converter_specific.clone();
+        if let Some(own) = self.per_converter.get(&converter.to_lowercase()) {
+            merged.extend(own.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        merged
+    }
+
+    /// Sets an option for every converter, replacing any value set for a single converter.
+    pub fn set_for_all(&mut self, name: &str, value: &str) {
+        self.converter_specific
+            .insert(String::from(name), String::from(value));
+        for own in self.per_converter.values_mut() {
+            own.remove(name);
         }
     }
 }
+
+/// Options for tests, converting as `input_type` with each `(name, value)` set for every converter.
+#[cfg(test)]
+pub(crate) fn test_options(input_type: &str, reverse: bool, settings: &[(&str, &str)]) -> Options {
+    let mut options = Options {
+        input_type: String::from(input_type),
+        reverse,
+        ..Options::default()
+    };
+    for (name, value) in settings {
+        options.set_for_all(name, value);
+    }
+    options
+}
+
+/// The kind of value a converter option takes.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum OptionType {
+    Bool,
+    Integer {
+        min: u8,
+        max: u8,
+    },
+    /// A single character, where `tab` or `\t` can be used for a tab.
+    Char,
+    Choice(&'static [&'static str]),
+}
+
+/// Describes an option a converter reads from `Options::converter_specific`, so values can be
+/// checked before converting and the help text can list them.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct OptionSchema {
+    pub name: &'static str,
+    pub option_type: OptionType,
+    /// `None` when the converter works the value out from the input.
+    pub default: Option<&'static str>,
+    pub help: &'static str,
+}
+
+impl OptionSchema {
+    pub fn validate(&self, value: &str) -> Result<(), String> {
+        let valid = match self.option_type {
+            OptionType::Bool => {
+                value.eq_ignore_ascii_case("true") || value.eq_ignore_ascii_case("false")
+            }
+            OptionType::Integer { min, max } => {
+                value.parse::<u8>().is_ok_and(|i| (min..=max).contains(&i))
+            }
+            OptionType::Char => {
+                value.eq_ignore_ascii_case("tab") || value == "\\t" || value.chars().count() == 1
+            }
+            OptionType::Choice(choices) => choices.iter().any(|c| c.eq_ignore_ascii_case(value)),
+        };
+
+        if valid {
+            Ok(())
+        } else {
+            Err(self.expected())
+        }
+    }
+
+    /// The values the option accepts, like "a number from 0 to 16".
+    pub fn expected(&self) -> String {
+        match self.option_type {
+            OptionType::Bool => String::from("true or false"),
+            OptionType::Integer { min, max } => format!("a number from {} to {}", min, max),
+            OptionType::Char => String::from("a single character or 'tab'"),
+            OptionType::Choice(choices) => format!("one of {}", choices.join(", ")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_converter_options() {
+        let mut options = Options::default();
+        options.set_for_all("indent", "4");
+        options.set_for_all("color", "true");
+        let mut json = HashMap::new();
+        json.insert(String::from("indent"), String::from("2"));
+        options.per_converter.insert(String::from("json"), json);
+
+        let json_options = options.converter_options("JSON");
+        assert_eq!(json_options.get("indent"), Some(&String::from("2")));
+        assert_eq!(json_options.get("color"), Some(&String::from("true")));
+        assert_eq!(
+            options.converter_options("XML").get("indent"),
+            Some(&String::from("4"))
+        );
+
+        options.set_for_all("indent", "8");
+        assert_eq!(
+            options.converter_options("JSON").get("indent"),
+            Some(&String::from("8"))
+        );
+    }
+
+    #[test]
+    fn test_option_schema_validate() {
+        let indent = OptionSchema {
+            name: "indent",
+            option_type: OptionType::Integer { min: 0, max: 16 },
+            default: Some("4"),
+            help: "",
+        };
+        assert_eq!(indent.validate("0"), Ok(()));
+        assert_eq!(indent.validate("16"), Ok(()));
+        assert_eq!(
+            indent.validate("17"),
+            Err(String::from("a number from 0 to 16"))
+        );
+        assert!(indent.validate("-1").is_err());
+        assert!(indent.validate("four").is_err());
+
+        let color = OptionSchema {
+            option_type: OptionType::Bool,
+            ..indent
+        };
+        assert_eq!(color.validate("TRUE"), Ok(()));
+        assert_eq!(color.validate("yes"), Err(String::from("true or false")));
+
+        let separator = OptionSchema {
+            option_type: OptionType::Char,
+            ..indent
+        };
+        assert_eq!(separator.validate(";"), Ok(()));
+        assert_eq!(separator.validate("tab"), Ok(()));
+        assert!(separator.validate(";;").is_err());
+
+        let alphabet = OptionSchema {
+            option_type: OptionType::Choice(&["standard", "url"]),
+            ..indent
+        };
+        assert_eq!(alphabet.validate("URL"), Ok(()));
+        assert_eq!(
+            alphabet.validate("mime"),
+            Err(String::from("one of standard, url"))
+        );
+    }
+}
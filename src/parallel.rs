@@ -0,0 +1,56 @@
+use rayon::prelude::*;
+use rayon::ThreadPool;
+
+/// How many items are processed in parallel before their results are handed on, which keeps the
+/// results in order without holding every one of them in memory.
+pub const CHUNK_SIZE: usize = 4096;
+
+/// Runs `f` on the items in parallel on the pool, and hands each result to `handle` in the order
+/// of the items.
+pub fn for_each_ordered<T, R, F, H>(pool: &ThreadPool, items: &[T], f: F, mut handle: H)
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+    H: FnMut(&T, R),
+{
+    for chunk in items.chunks(CHUNK_SIZE) {
+        let results: Vec<R> = pool.install(|| chunk.par_iter().map(&f).collect());
+        for (item, result) in chunk.iter().zip(results) {
+            handle(item, result);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn test_for_each_ordered() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+        let items: Vec<usize> = (0..CHUNK_SIZE * 2 + 10).collect();
+
+        let mut handled = Vec::new();
+        for_each_ordered(
+            &pool,
+            &items,
+            |i| {
+                // Early items finish last, so the results only stay in order if they are sorted.
+                if i % CHUNK_SIZE < 4 {
+                    thread::sleep(Duration::from_millis(20));
+                }
+                i * 2
+            },
+            |i, result| handled.push((*i, result)),
+        );
+
+        let expected: Vec<(usize, usize)> = items.iter().map(|i| (*i, i * 2)).collect();
+        assert_eq!(handled, expected);
+    }
+}
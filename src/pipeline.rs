@@ -0,0 +1,173 @@
+use crate::converters;
+use crate::error::Error;
+use crate::options::Options;
+
+pub fn prettify(input: &[u8], options: &Options) -> Result<String, Error> {
+    run(input, options, crate::prettify)
+}
+
+pub fn prettify_bytes(input: &[u8], options: &Options) -> Result<Vec<u8>, Error> {
+    run(input, options, crate::prettify_bytes)
+}
+
+fn run<T>(
+    input: &[u8],
+    options: &Options,
+    last_stage: fn(&[u8], &Options) -> Result<T, Error>,
+) -> Result<T, Error> {
+    let (last_type, stage_types) = match options.pipeline.split_last() {
+        Some(split) => split,
+        None => return last_stage(input, options),
+    };
+    check_stage_types(&options.pipeline)?;
+
+    let mut stage_input = input.to_vec();
+    for (i, input_type) in stage_types.iter().enumerate() {
+        if options.verbose {
+            println!("Pipeline stage {}: {}", i + 1, input_type);
+        }
+
+        match crate::prettify_bytes(&stage_input, &stage_options(options, input_type, false)) {
+            Ok(output) => stage_input = output,
+            Err(e) => return Err(stage_failed(i + 1, input_type, stage_input, e)),
+        }
+    }
+
+    let stage = options.pipeline.len();
+    if options.verbose {
+        println!("Pipeline stage {}: {}", stage, last_type);
+    }
+    last_stage(&stage_input, &stage_options(options, last_type, true))
+        .map_err(|e| stage_failed(stage, last_type, stage_input, e))
+}
+
+/// Checks every stage is a known input type before any of them runs, so a typo in a late stage
+/// does not waste the work of the earlier ones.
+fn check_stage_types(stage_types: &[String]) -> Result<(), Error> {
+    let converters = converters::get_converters();
+    for input_type in stage_types {
+        let known = input_type.eq_ignore_ascii_case("all")
+            || converters
+                .iter()
+                .any(|c| input_type.eq_ignore_ascii_case(c.name()));
+        if !known {
+            return Err(Error::UnknownInputType(input_type.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+fn stage_failed(stage: usize, input_type: &str, input: Vec<u8>, cause: Error) -> Error {
+    Error::StageFailed {
+        stage,
+        input_type: String::from(input_type),
+        input,
+        cause: Box::new(cause),
+    }
+}
+
+fn stage_options(options: &Options, input_type: &str, is_last: bool) -> Options {
+    let mut stage = options.clone();
+    stage.input_type = String::from(input_type);
+    stage.pipeline = Vec::new();
+    if !is_last {
+        stage.recursion_depth = 0;
+        stage.set_for_all("color", "false");
+    }
+    stage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::test_options;
+
+    #[test]
+    fn test_prettify_pipeline() {
+        assert_eq!(
+            prettify(
+                "eyJhIjpbMSwyXX0=".as_bytes(),
+                &Options {
+                    pipeline: vec![String::from("base64"), String::from("json")],
+                    ..test_options("all", false, &[("color", "false"), ("indent", "2")])
+                }
+            ),
+            Ok(String::from("{\n  \"a\": [\n    1,\n    2\n  ]\n}"))
+        );
+        assert_eq!(
+            prettify(
+                "{\n  \"a\": [1, 2]\n}".as_bytes(),
+                &Options {
+                    pipeline: vec![String::from("json"), String::from("base64")],
+                    ..test_options("all", true, &[("color", "false"), ("indent", "2")])
+                }
+            ),
+            Ok(String::from("eyJhIjpbMSwyXX0="))
+        );
+    }
+
+    #[test]
+    fn test_prettify_pipeline_binary_stage() {
+        // Base64 of `{"a":[1,2]}` as UTF-16 with a byte order mark.
+        assert_eq!(
+            prettify(
+                "//57ACIAYQAiADoAWwAxACwAMgBdAH0A".as_bytes(),
+                &Options {
+                    pipeline: vec![String::from("base64"), String::from("json")],
+                    ..test_options("all", false, &[("color", "false"), ("indent", "2")])
+                }
+            ),
+            Ok(String::from("{\n  \"a\": [\n    1,\n    2\n  ]\n}"))
+        );
+    }
+
+    #[test]
+    fn test_prettify_pipeline_stage_failed() {
+        assert_eq!(
+            prettify(
+                "aGVsbG8=".as_bytes(),
+                &Options {
+                    pipeline: vec![String::from("base64"), String::from("json")],
+                    ..test_options("all", false, &[("color", "false"), ("indent", "2")])
+                }
+            ),
+            Err(Error::StageFailed {
+                stage: 2,
+                input_type: String::from("json"),
+                input: b"hello".to_vec(),
+                cause: Box::new(Error::NoConverterMatched(vec![Error::cannot_convert_at(
+                    "JSON",
+                    "expected value",
+                    crate::error::Location {
+                        line: 1,
+                        column: 1,
+                        offset: 0
+                    }
+                )])),
+            })
+        );
+        assert_eq!(
+            prettify(
+                "dGVzdA==".as_bytes(),
+                &Options {
+                    pipeline: vec![
+                        String::from("base64"),
+                        String::from("toml"),
+                        String::from("json")
+                    ],
+                    ..test_options("all", false, &[("color", "false"), ("indent", "2")])
+                }
+            ),
+            Err(Error::UnknownInputType(String::from("toml")))
+        );
+        assert!(prettify(
+            "eyJhIjpbMSwyXX0=".as_bytes(),
+            &Options {
+                pipeline: vec![String::from("base64"), String::from("all")],
+                ..test_options("all", false, &[("color", "false"), ("indent", "2")])
+            }
+        )
+        .is_ok());
+    }
+}
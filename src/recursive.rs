@@ -0,0 +1,210 @@
+use crate::converters::base64_converter::Base64Converter;
+use crate::converters::Converter;
+use crate::error::Error;
+use crate::options::Options;
+use serde_json::Value;
+
+const JSON_NAME: &str = "JSON";
+
+pub fn prettify(input: &[u8], options: &Options) -> Result<String, Error> {
+    let (mut output, mut converter) =
+        crate::convert(input, &plain_options(options, &options.input_type))?;
+    let mut stage_input = input.to_vec();
+    let mut path = vec![converter.clone()];
+
+    for _ in 0..options.recursion_depth {
+        match crate::convert(output.as_bytes(), &plain_options(options, "all")) {
+            Ok((next_output, next_converter)) => {
+                if next_converter == converter && next_output == output {
+                    break;
+                }
+                stage_input = output.into_bytes();
+                output = next_output;
+                converter = next_converter;
+                path.push(converter.clone());
+            }
+            Err(_) => break,
+        }
+    }
+
+    if options.verbose {
+        println!("Expansion path: {}", path.join(" -> "));
+    }
+
+    let mut final_options = options.clone();
+    final_options.input_type = converter.clone();
+    if converter == JSON_NAME {
+        let remaining_depth = options.recursion_depth.saturating_sub(path.len() as u8 - 1);
+        let expanded = expand_json(&stage_input, remaining_depth, options)?;
+        crate::convert(&expanded, &final_options).map(|(output, _)| output)
+    } else {
+        crate::convert(&stage_input, &final_options).map(|(output, _)| output)
+    }
+}
+
+fn expand_json(input: &[u8], depth: u8, options: &Options) -> Result<Vec<u8>, Error> {
+    match serde_json::from_slice::<Value>(input) {
+        Ok(mut value) => {
+            expand_value(&mut value, depth, "$", options);
+            Ok(value.to_string().into_bytes())
+        }
+        Err(_) => Ok(input.to_vec()),
+    }
+}
+
+fn expand_value(value: &mut Value, depth: u8, path: &str, options: &Options) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                expand_value(child, depth, &format!("{}.{}", path, key), options);
+            }
+        }
+        Value::Array(values) => {
+            for (i, child) in values.iter_mut().enumerate() {
+                expand_value(child, depth, &format!("{}[{}]", path, i), options);
+            }
+        }
+        Value::String(s) => {
+            if let Some((expanded, stages)) = decode_string(s, depth, path, options) {
+                if options.verbose {
+                    println!("Expanded {}: {}", path, stages.join(" -> "));
+                }
+                *value = expanded;
+            }
+        }
+        _ => (),
+    }
+}
+
+fn decode_string(
+    input: &str,
+    depth: u8,
+    path: &str,
+    options: &Options,
+) -> Option<(Value, Vec<String>)> {
+    if depth == 0 {
+        return None;
+    }
+
+    if let Ok(mut parsed) = serde_json::from_str::<Value>(input) {
+        if parsed.is_object() || parsed.is_array() {
+            expand_value(&mut parsed, depth - 1, path, options);
+            return Some((parsed, vec![String::from(JSON_NAME)]));
+        }
+    }
+
+    let base64 = Base64Converter::new();
+    let decoded = base64
+        .prettify(input.as_bytes(), &plain_options(options, base64.name()))
+        .ok()?;
+    let (value, mut stages) = decode_string(&decoded, depth - 1, path, options)?;
+    stages.insert(0, String::from(base64.name()));
+    Some((value, stages))
+}
+
+fn plain_options(options: &Options, input_type: &str) -> Options {
+    let mut plain = options.clone();
+    plain.input_type = String::from(input_type);
+    plain.verbose = false;
+    plain.reverse = false;
+    plain.recursion_depth = 0;
+    plain.set_for_all("color", "false");
+    plain
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::test_options;
+
+    #[test]
+    fn test_prettify_nested_top_level() {
+        // {"a":1} encoded twice
+        let input = "ZXlKaElqb3hmUT09";
+
+        assert_eq!(
+            prettify(
+                input.as_bytes(),
+                &Options {
+                    recursion_depth: 3,
+                    ..test_options("all", false, &[("color", "false"), ("indent", "2")])
+                }
+            ),
+            Ok(String::from("{\n  \"a\": 1\n}"))
+        );
+        assert_eq!(
+            prettify(
+                input.as_bytes(),
+                &Options {
+                    recursion_depth: 1,
+                    ..test_options("all", false, &[("color", "false"), ("indent", "2")])
+                }
+            ),
+            Ok(String::from("{\"a\":1}"))
+        );
+    }
+
+    #[test]
+    fn test_prettify_nested_strings() {
+        let input = r#"{"claims":"eyJzdWIiOiJtZSJ9","raw":"{\"b\":[1]}","text":"abcd","n":1}"#;
+
+        assert_eq!(
+            prettify(
+                input.as_bytes(),
+                &Options {
+                    recursion_depth: 3,
+                    ..test_options("all", false, &[("color", "false"), ("indent", "2")])
+                }
+            ),
+            Ok(String::from(
+                r#"{
+  "claims": {
+    "sub": "me"
+  },
+  "raw": {
+    "b": [
+      1
+    ]
+  },
+  "text": "abcd",
+  "n": 1
+}"#
+            ))
+        );
+        assert_eq!(
+            prettify(
+                input.as_bytes(),
+                &Options {
+                    recursion_depth: 1,
+                    ..test_options("all", false, &[("color", "false"), ("indent", "2")])
+                }
+            ),
+            Ok(String::from(
+                r#"{
+  "claims": "eyJzdWIiOiJtZSJ9",
+  "raw": {
+    "b": [
+      1
+    ]
+  },
+  "text": "abcd",
+  "n": 1
+}"#
+            ))
+        );
+    }
+
+    #[test]
+    fn test_prettify_plain_text_result() {
+        assert_eq!(
+            prettify(
+                "aGVsbG8gd29ybGQh".as_bytes(),
+                &Options {
+                    recursion_depth: 3,
+                    ..test_options("all", false, &[("color", "false"), ("indent", "2")])
+                }
+            ),
+            Ok(String::from("hello world!"))
+        );
+    }
+}
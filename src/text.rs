@@ -0,0 +1,84 @@
+use std::borrow::Cow;
+use std::char;
+use std::str;
+
+/// Decodes input as text. A byte order mark picks between UTF-8 and UTF-16, and UTF-16 without
+/// one is recognized when every other byte is zero, as it is for mostly ASCII text.
+pub fn decode(input: &[u8]) -> Option<Cow<'_, str>> {
+    if let Some(rest) = input.strip_prefix(b"\xef\xbb\xbf") {
+        return str::from_utf8(rest).ok().map(Cow::Borrowed);
+    }
+    if let Some(rest) = input.strip_prefix(b"\xff\xfe") {
+        return decode_utf16(rest, u16::from_le_bytes);
+    }
+    if let Some(rest) = input.strip_prefix(b"\xfe\xff") {
+        return decode_utf16(rest, u16::from_be_bytes);
+    }
+
+    if has_zero_bytes_at(input, 1) {
+        decode_utf16(input, u16::from_le_bytes)
+    } else if has_zero_bytes_at(input, 0) {
+        decode_utf16(input, u16::from_be_bytes)
+    } else {
+        str::from_utf8(input).ok().map(Cow::Borrowed)
+    }
+}
+
+/// Whether the input starts like UTF-16 text, with a byte order mark or with a zero byte in its
+/// first character.
+pub fn is_utf16(start: &[u8]) -> bool {
+    start.starts_with(b"\xff\xfe")
+        || start.starts_with(b"\xfe\xff")
+        || (start.len() >= 2 && (start[0] == 0) != (start[1] == 0))
+}
+
+fn has_zero_bytes_at(input: &[u8], position: usize) -> bool {
+    input.len() >= 2
+        && input.len().is_multiple_of(2)
+        && input
+            .chunks_exact(2)
+            .all(|pair| pair[position] == 0 && pair[1 - position] != 0)
+}
+
+fn decode_utf16(input: &[u8], to_u16: fn([u8; 2]) -> u16) -> Option<Cow<'_, str>> {
+    if !input.len().is_multiple_of(2) {
+        return None;
+    }
+
+    char::decode_utf16(input.chunks_exact(2).map(|pair| to_u16([pair[0], pair[1]])))
+        .collect::<Result<String, _>>()
+        .ok()
+        .map(Cow::Owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_utf8() {
+        assert_eq!(decode(b"{\"a\": 1}\r\n"), Some(Cow::from("{\"a\": 1}\r\n")));
+        assert_eq!(decode(b"\xef\xbb\xbfs\xc3\xa9"), Some(Cow::from("sé")));
+        assert_eq!(decode(b""), Some(Cow::from("")));
+        assert_eq!(decode(b"\xff\x00\x41"), None);
+    }
+
+    #[test]
+    fn test_decode_utf16() {
+        assert_eq!(decode(b"\xff\xfe[\x001\x00]\x00"), Some(Cow::from("[1]")));
+        assert_eq!(decode(b"\xfe\xff\x00[\x001\x00]"), Some(Cow::from("[1]")));
+        assert_eq!(decode(b"[\x001\x00]\x00"), Some(Cow::from("[1]")));
+        assert_eq!(decode(b"\x00[\x001\x00]"), Some(Cow::from("[1]")));
+        assert_eq!(decode(b"\xff\xfe[\x00\x00"), None);
+        assert_eq!(decode(b"\xff\xfe\x00\xd8"), None);
+    }
+
+    #[test]
+    fn test_is_utf16() {
+        assert!(is_utf16(b"\xff\xfe[\x00"));
+        assert!(is_utf16(b"\x00[\x001"));
+        assert!(!is_utf16(b"\xef\xbb\xbf[1]"));
+        assert!(!is_utf16(b"[1]"));
+        assert!(!is_utf16(b""));
+    }
+}
@@ -0,0 +1,320 @@
+use colored_json::{Color, Style, Styler};
+use std::collections::HashMap;
+
+pub const BUILT_IN_THEMES: [&str; 4] = ["default", "light", "dark", "high-contrast"];
+
+/// The kinds of tokens converters color, each getting its style from the theme.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum TokenKind {
+    Key,
+    String,
+    Number,
+    Boolean,
+    Null,
+    Punctuation,
+    Tag,
+    Attribute,
+    Comment,
+    Declaration,
+    CData,
+}
+
+impl TokenKind {
+    pub const ALL: [TokenKind; 11] = [
+        TokenKind::Key,
+        TokenKind::String,
+        TokenKind::Number,
+        TokenKind::Boolean,
+        TokenKind::Null,
+        TokenKind::Punctuation,
+        TokenKind::Tag,
+        TokenKind::Attribute,
+        TokenKind::Comment,
+        TokenKind::Declaration,
+        TokenKind::CData,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TokenKind::Key => "key",
+            TokenKind::String => "string",
+            TokenKind::Number => "number",
+            TokenKind::Boolean => "boolean",
+            TokenKind::Null => "null",
+            TokenKind::Punctuation => "punctuation",
+            TokenKind::Tag => "tag",
+            TokenKind::Attribute => "attribute",
+            TokenKind::Comment => "comment",
+            TokenKind::Declaration => "declaration",
+            TokenKind::CData => "cdata",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TokenKind> {
+        TokenKind::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.name() == name)
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Theme {
+    pub name: String,
+    styles: HashMap<TokenKind, Style>,
+    light_background: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Theme {
+        Theme::new(
+            "default",
+            [
+                (TokenKind::Key, Color::Blue.bold()),
+                (TokenKind::String, Style::new().fg(Color::Green)),
+                (TokenKind::Punctuation, Style::new().bold()),
+                (TokenKind::Tag, Color::Blue.bold()),
+                (TokenKind::Attribute, Style::new().fg(Color::Cyan)),
+                (TokenKind::Comment, Style::new().dimmed()),
+                (TokenKind::Declaration, Style::new().fg(Color::Purple)),
+                (TokenKind::CData, Style::new().fg(Color::Yellow)),
+            ],
+        )
+    }
+}
+
+impl Theme {
+    fn new<const N: usize>(name: &str, styles: [(TokenKind, Style); N]) -> Theme {
+        Theme {
+            name: String::from(name),
+            styles: styles.iter().copied().collect(),
+            light_background: name == "light",
+        }
+    }
+
+    pub fn built_in(name: &str) -> Option<Theme> {
+        let theme = match name.to_lowercase().as_str() {
+            "default" => Theme::default(),
+            "light" => Theme::new(
+                "light",
+                [
+                    (TokenKind::Key, Color::Blue.bold()),
+                    (TokenKind::String, Style::new().fg(Color::Green)),
+                    (TokenKind::Number, Style::new().fg(Color::Purple)),
+                    (TokenKind::Boolean, Style::new().fg(Color::Red)),
+                    (TokenKind::Null, Style::new().fg(Color::Red)),
+                    (TokenKind::Punctuation, Color::Black.bold()),
+                    (TokenKind::Tag, Color::Blue.bold()),
+                    (TokenKind::Attribute, Style::new().fg(Color::Purple)),
+                    (TokenKind::Comment, Style::new().dimmed().italic()),
+                    (TokenKind::Declaration, Style::new().fg(Color::Red)),
+                    (TokenKind::CData, Style::new().fg(Color::Green)),
+                ],
+            ),
+            "dark" => Theme::new(
+                "dark",
+                [
+                    (TokenKind::Key, Color::Cyan.bold()),
+                    (TokenKind::String, Style::new().fg(Color::Green)),
+                    (TokenKind::Number, Style::new().fg(Color::Yellow)),
+                    (TokenKind::Boolean, Style::new().fg(Color::Purple)),
+                    (TokenKind::Null, Style::new().fg(Color::Red)),
+                    (TokenKind::Punctuation, Color::White.bold()),
+                    (TokenKind::Tag, Color::Cyan.bold()),
+                    (TokenKind::Attribute, Style::new().fg(Color::Yellow)),
+                    (TokenKind::Comment, Style::new().dimmed().italic()),
+                    (TokenKind::Declaration, Style::new().fg(Color::Purple)),
+                    (TokenKind::CData, Style::new().fg(Color::Green)),
+                ],
+            ),
+            "high-contrast" => Theme::new(
+                "high-contrast",
+                [
+                    (TokenKind::Key, Color::Fixed(11).bold()),
+                    (TokenKind::String, Style::new().fg(Color::Fixed(10))),
+                    (TokenKind::Number, Style::new().fg(Color::Fixed(14))),
+                    (TokenKind::Boolean, Style::new().fg(Color::Fixed(13))),
+                    (TokenKind::Null, Style::new().fg(Color::Fixed(9))),
+                    (TokenKind::Punctuation, Color::Fixed(15).bold()),
+                    (TokenKind::Tag, Color::Fixed(14).bold()),
+                    (TokenKind::Attribute, Style::new().fg(Color::Fixed(11))),
+                    (TokenKind::Comment, Style::new().fg(Color::White)),
+                    (TokenKind::Declaration, Style::new().fg(Color::Fixed(13))),
+                    (TokenKind::CData, Style::new().fg(Color::Fixed(10))),
+                ],
+            ),
+            _ => return None,
+        };
+        Some(theme)
+    }
+
+    /// Builds a theme on top of another one, with styles given by token kind name, like
+    /// `key = "bold yellow"`.
+    pub fn custom(
+        name: &str,
+        base: &Theme,
+        styles: &HashMap<String, String>,
+    ) -> Result<Theme, String> {
+        let mut theme = base.clone();
+        theme.name = String::from(name);
+        for (kind_name, spec) in styles {
+            let kind = TokenKind::from_name(kind_name).ok_or_else(|| {
+                format!(
+                    "'{}' is not a token kind, it must be one of {}",
+                    kind_name,
+                    TokenKind::ALL.map(TokenKind::name).join(", ")
+                )
+            })?;
+            theme.styles.insert(kind, parse_style(spec)?);
+        }
+
+        Ok(theme)
+    }
+
+    pub fn style(&self, kind: TokenKind) -> Style {
+        self.styles.get(&kind).copied().unwrap_or_default()
+    }
+
+    /// Whether the theme is meant for a light background, which themes built on it inherit.
+    pub fn light_background(&self) -> bool {
+        self.light_background
+    }
+
+    pub fn paint(&self, kind: TokenKind, text: &str) -> String {
+        self.style(kind).paint(text).to_string()
+    }
+
+    /// The styles for the `colored_json` formatter.
+    pub fn json_styler(&self) -> Styler {
+        Styler {
+            object_brackets: self.style(TokenKind::Punctuation),
+            array_brackets: self.style(TokenKind::Punctuation),
+            key: self.style(TokenKind::Key),
+            string_value: self.style(TokenKind::String),
+            integer_value: self.style(TokenKind::Number),
+            float_value: self.style(TokenKind::Number),
+            bool_value: self.style(TokenKind::Boolean),
+            nil_value: self.style(TokenKind::Null),
+            string_include_quotation: true,
+        }
+    }
+}
+
+/// Parses a style like "bold cyan", "underline #ff8800 on black" or "208". Colors are the eight
+/// terminal color names, their `bright-` versions, a number from the 256 color palette or a hex
+/// RGB value. "plain" is a style without color.
+pub fn parse_style(spec: &str) -> Result<Style, String> {
+    let mut style = Style::new();
+    let mut words = spec.split_whitespace();
+    while let Some(word) = words.next() {
+        style = match word.to_lowercase().as_str() {
+            "plain" | "none" => style,
+            "bold" => style.bold(),
+            "dim" | "dimmed" => style.dimmed(),
+            "italic" => style.italic(),
+            "underline" => style.underline(),
+            "on" => match words.next() {
+                Some(background) => style.on(parse_color(background)?),
+                None => return Err(format!("'{}' is missing a background color", spec)),
+            },
+            color => style.fg(parse_color(color)?),
+        };
+    }
+
+    Ok(style)
+}
+
+fn parse_color(name: &str) -> Result<Color, String> {
+    const COLORS: [(&str, Color); 8] = [
+        ("black", Color::Black),
+        ("red", Color::Red),
+        ("green", Color::Green),
+        ("yellow", Color::Yellow),
+        ("blue", Color::Blue),
+        ("purple", Color::Purple),
+        ("cyan", Color::Cyan),
+        ("white", Color::White),
+    ];
+    let name = name.to_lowercase();
+    let name = if name == "magenta" { "purple" } else { &name };
+
+    if let Some((_, color)) = COLORS.iter().find(|(n, _)| *n == name) {
+        return Ok(*color);
+    }
+    if let Some(i) = name
+        .strip_prefix("bright-")
+        .and_then(|base| COLORS.iter().position(|(n, _)| *n == base))
+    {
+        return Ok(Color::Fixed(i as u8 + 8));
+    }
+    if let Ok(i) = name.parse::<u8>() {
+        return Ok(Color::Fixed(i));
+    }
+    if let Some(hex) = name.strip_prefix('#') {
+        if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+            return Ok(Color::RGB(channel(0), channel(2), channel(4)));
+        }
+    }
+
+    Err(format!("'{}' is not a color", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_style() {
+        assert_eq!(parse_style("bold cyan"), Ok(Color::Cyan.bold()));
+        assert_eq!(parse_style("plain"), Ok(Style::new()));
+        assert_eq!(
+            parse_style("Underline #FF8800 on bright-black"),
+            Ok(Style::new()
+                .underline()
+                .fg(Color::RGB(255, 136, 0))
+                .on(Color::Fixed(8)))
+        );
+        assert_eq!(
+            parse_style("magenta italic"),
+            Ok(Style::new().fg(Color::Purple).italic())
+        );
+        assert_eq!(parse_style("208"), Ok(Style::new().fg(Color::Fixed(208))));
+        assert_eq!(
+            parse_style("bold teal"),
+            Err(String::from("'teal' is not a color"))
+        );
+        assert!(parse_style("red on").is_err());
+        assert!(parse_style("#12345").is_err());
+    }
+
+    #[test]
+    fn test_built_in_themes() {
+        for name in BUILT_IN_THEMES {
+            assert_eq!(Theme::built_in(name).unwrap().name, name);
+        }
+        assert_eq!(Theme::built_in("solarized"), None);
+        assert_eq!(
+            Theme::default().paint(TokenKind::Key, "a"),
+            "\u{1b}[1;34ma\u{1b}[0m"
+        );
+        assert_eq!(Theme::default().paint(TokenKind::Number, "1"), "1");
+    }
+
+    #[test]
+    fn test_custom_theme() {
+        let mut styles = HashMap::new();
+        styles.insert(String::from("key"), String::from("bold yellow"));
+        let theme = Theme::custom("mine", &Theme::built_in("dark").unwrap(), &styles).unwrap();
+
+        assert_eq!(theme.name, "mine");
+        assert_eq!(theme.style(TokenKind::Key), Color::Yellow.bold());
+        assert_eq!(
+            theme.style(TokenKind::String),
+            Style::new().fg(Color::Green)
+        );
+
+        styles.insert(String::from("keys"), String::from("red"));
+        assert!(Theme::custom("mine", &Theme::default(), &styles).is_err());
+    }
+}